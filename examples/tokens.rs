@@ -7,6 +7,7 @@ fn main() {
     let result = normalizer.tokenize(name).unwrap();
 
     for token in result.tokens {
+        println!("{}", token.debug_pretty());
         if token.is_disallowed() {
             println!("disallowed: {:?}", token.as_string());
         }
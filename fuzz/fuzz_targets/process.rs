@@ -0,0 +1,21 @@
+#![no_main]
+
+use ens_normalize_rs::EnsNameNormalizer;
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+static NORMALIZER: OnceLock<EnsNameNormalizer> = OnceLock::new();
+
+fuzz_target!(|input: &str| {
+    let normalizer = NORMALIZER.get_or_init(EnsNameNormalizer::default);
+    // arbitrary input may fail, but never panic
+    let _ = normalizer.tokenize(input);
+    if let Ok(processed) = normalizer.process(input) {
+        // normalization is idempotent
+        let normalized = processed.normalize();
+        assert_eq!(
+            normalizer.normalize(&normalized).as_deref(),
+            Ok(normalized.as_str())
+        );
+    }
+});
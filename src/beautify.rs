@@ -8,6 +8,8 @@ pub fn beautify_labels(labels: &[ValidatedLabel]) -> String {
         label
             .tokens
             .iter()
+            // tokens are handled independently, so emoji next to ξ keep FE0F
+            // and ξ is replaced based on the label type only
             .filter_map(|token| match token {
                 EnsNameToken::Emoji(emoji) => Some(emoji.emoji.clone()),
                 EnsNameToken::Valid(_) | EnsNameToken::Mapped(_) | EnsNameToken::Nfc(_) => {
@@ -34,3 +36,32 @@ fn cps_replaced_greek(mut cps: Vec<CodePoint>, label_type: &LabelType) -> Vec<Co
 
     cps
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate::validate_name, CodePointsSpecs, TokenizedName};
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    #[once]
+    fn specs() -> CodePointsSpecs {
+        CodePointsSpecs::default()
+    }
+
+    #[rstest]
+    #[case::xi_with_keycap("-ξ1⃣", "-Ξ1\u{FE0F}⃣")]
+    #[case::keycap_before_xi("1⃣ξ", "1\u{FE0F}⃣Ξ")]
+    #[case::xi_between_emoji("💩ξ💩", "💩\u{FE0F}Ξ💩\u{FE0F}")]
+    #[case::greek_label_keeps_xi("φιλοξενία", "φιλοξενία")]
+    fn test_beautify_xi_and_emoji(
+        #[case] input: &str,
+        #[case] expected: &str,
+        specs: &CodePointsSpecs,
+    ) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        let labels = validate_name(&name, specs, &Default::default()).unwrap();
+        assert_eq!(beautify_labels(&labels), expected);
+    }
+}
@@ -0,0 +1,56 @@
+//! Reads ENS names from stdin, one per line, and prints a JSON object per name:
+//! `{"input": ..., "normalized": ..., "beautified": ...}` on success or
+//! `{"input": ..., "error": {"code": ..., "index": ..., "message": ...}}` on failure.
+//! Exits with non-zero status if any of the names is invalid.
+
+use ens_normalize_rs::{CodePointsSpecs, DisallowedSequence, EnsNameNormalizer, ProcessError};
+use std::io::{self, BufRead, Write};
+
+fn main() -> io::Result<()> {
+    let normalizer = EnsNameNormalizer::default();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    let mut failed = false;
+
+    for line in stdin.lock().lines() {
+        let input = line?;
+        let output = match normalizer.process(&input) {
+            Ok(processed) => serde_json::json!({
+                "input": input,
+                "normalized": processed.normalize(),
+                "beautified": processed.beautify(),
+            }),
+            Err(err) => {
+                failed = true;
+                serde_json::json!({
+                    "input": input,
+                    "error": {
+                        "code": err.code(),
+                        "index": err.index(),
+                        "message": error_message(&err, normalizer.specs()),
+                    },
+                })
+            }
+        };
+        writeln!(stdout, "{output}")?;
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Error message with disallowed characters annotated with their Unicode block
+fn error_message(err: &ProcessError, specs: &CodePointsSpecs) -> String {
+    match err {
+        ProcessError::DisallowedSequence(DisallowedSequence::Invalid { sequence, .. }) => {
+            let described = sequence
+                .chars()
+                .map(|c| specs.describe_cp(c as u32))
+                .collect::<Vec<_>>();
+            format!("{err} [{}]", described.join(", "))
+        }
+        _ => err.to_string(),
+    }
+}
@@ -0,0 +1,95 @@
+use super::CodePointsSpecs;
+use crate::{
+    static_data::{nf_json, spec_json},
+    utils, CodePoint,
+};
+
+/// Builds [`CodePointsSpecs`] from spec data with custom additions on top.
+///
+/// Additions diverge from ENSIP-15: names using them are not normalized
+/// the way ENS resolvers expect and won't resolve on mainnet.
+#[derive(Debug, Clone, Default)]
+pub struct CodePointsSpecsBuilder {
+    spec: spec_json::Spec,
+    nf: nf_json::Nf,
+    custom_emoji: Vec<Vec<CodePoint>>,
+}
+
+impl CodePointsSpecsBuilder {
+    /// Starts from the given `spec.json` and `nf.json` data.
+    /// Use [`CodePointsSpecsBuilder::default`] to start from the bundled spec.
+    pub fn new(spec: spec_json::Spec, nf: nf_json::Nf) -> Self {
+        Self {
+            spec,
+            nf,
+            custom_emoji: vec![],
+        }
+    }
+
+    /// Accept an emoji sequence missing from the spec, e.g. a brand emoji.
+    /// `cps` is the fully-qualified form, with `FE0F` if any; it is also matched without `FE0F`.
+    /// Labels made of such emoji validate as [`crate::LabelType::Emoji`].
+    pub fn add_emoji(mut self, cps: Vec<CodePoint>) -> Self {
+        self.custom_emoji.push(cps);
+        self
+    }
+
+    /// Builds the specs, rebuilding the emoji matcher with the added emoji.
+    /// Custom emoji change [`CodePointsSpecs::fingerprint`], so the result
+    /// never passes as the unmodified spec.
+    pub fn build(mut self) -> anyhow::Result<CodePointsSpecs> {
+        let custom_emoji = std::mem::take(&mut self.custom_emoji);
+        self.spec.emoji.extend(custom_emoji.iter().cloned());
+        let mut specs = CodePointsSpecs::new(self.spec, self.nf)?;
+        if !custom_emoji.is_empty() {
+            let parts = custom_emoji
+                .iter()
+                .map(|cps| utils::cps2str(cps))
+                .collect::<Vec<_>>();
+            specs.extend_fingerprint(&parts);
+        }
+        Ok(specs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnsNameNormalizer, EnsNameToken, LabelType};
+    use pretty_assertions::assert_eq;
+
+    // private use code point, as a brand emoji would be
+    const CUSTOM_EMOJI: CodePoint = 0xE000;
+
+    #[test]
+    fn test_add_emoji() {
+        let specs = CodePointsSpecsBuilder::default()
+            .add_emoji(vec![CUSTOM_EMOJI])
+            .build()
+            .unwrap();
+        assert!(specs.cps_is_emoji(&[CUSTOM_EMOJI]));
+        assert_ne!(
+            specs.fingerprint(),
+            CodePointsSpecs::default().fingerprint()
+        );
+
+        let name = "\u{E000}\u{E000}.eth";
+        assert!(EnsNameNormalizer::default().process(name).is_err());
+        let processed = EnsNameNormalizer::new(specs).process(name).unwrap();
+        assert_eq!(processed.normalize(), name);
+        assert_eq!(processed.labels[0].label_type, LabelType::Emoji);
+        assert!(processed.labels[0]
+            .tokens
+            .iter()
+            .all(EnsNameToken::is_emoji));
+    }
+
+    #[test]
+    fn test_build_without_additions() {
+        let specs = CodePointsSpecsBuilder::default().build().unwrap();
+        assert_eq!(
+            specs.fingerprint(),
+            CodePointsSpecs::default().fingerprint()
+        );
+    }
+}
@@ -1,5 +1,9 @@
+mod blocks;
+mod builder;
+mod presentation;
 mod specs;
 mod types;
 
+pub use builder::CodePointsSpecsBuilder;
 pub use specs::CodePointsSpecs;
 pub use types::*;
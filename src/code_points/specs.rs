@@ -1,12 +1,18 @@
-use super::types::*;
+use super::{blocks, presentation, types::*};
 use crate::{
     constants,
+    join::join_labels_cps,
     static_data::{
         nf_json,
         spec_json::{self, GroupName},
     },
-    utils, CodePoint,
+    tokens::process_one_cp,
+    utils,
+    validate::{LabelValidator, ValidationOptions},
+    CodePoint, DisallowedSequence, EnsNameToken, ProcessError,
 };
+use anyhow::Context;
+use itertools::Itertools;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 
@@ -16,7 +22,9 @@ pub struct CodePointsSpecs {
     ignored: HashSet<CodePoint>,
     mapped: HashMap<CodePoint, Vec<CodePoint>>,
     nfc_check: HashSet<CodePoint>,
+    escape: HashSet<CodePoint>,
     whole_map: ParsedWholeMap,
+    confusable_targets: HashMap<CodePoint, Vec<CodePoint>>,
     fenced: HashMap<CodePoint, String>,
     groups: Vec<ParsedGroup>,
     group_name_to_index: HashMap<spec_json::GroupName, usize>,
@@ -24,22 +32,44 @@ pub struct CodePointsSpecs {
     nsm: HashSet<CodePoint>,
     nsm_max: u32,
     emoji_no_fe0f_to_pretty: HashMap<Vec<CodePoint>, Vec<CodePoint>>,
+    /// Values of `emoji_no_fe0f_to_pretty`, sorted
+    emoji_pretty_sorted: Vec<Vec<CodePoint>>,
     decomp: HashMap<CodePoint, Vec<CodePoint>>,
+    ranks: HashMap<CodePoint, u8>,
+    qc: HashSet<CodePoint>,
     emoji_regex: Regex,
+    fingerprint: String,
+    unicode_version: String,
+    cldr_version: String,
+    created: String,
 }
 
 impl CodePointsSpecs {
-    pub fn new(spec: spec_json::Spec, nf: nf_json::Nf) -> Self {
+    /// Builds specs from parsed `spec.json` and `nf.json` data.
+    /// Returns an error instead of panicking if the data is inconsistent.
+    pub fn new(spec: spec_json::Spec, nf: nf_json::Nf) -> anyhow::Result<Self> {
         let emoji: HashSet<Vec<CodePoint>> = spec.emoji.into_iter().collect();
-        let emoji_no_fe0f_to_pretty = emoji
+        let emoji_no_fe0f_to_pretty: HashMap<_, _> = emoji
             .iter()
             .map(|e| (utils::filter_fe0f(e), e.clone()))
             .collect();
+        let emoji_pretty_sorted = emoji_no_fe0f_to_pretty.values().cloned().sorted().collect();
         let decomp = nf
             .decomp
             .into_iter()
             .map(|item| (item.number, item.nested_numbers))
             .collect();
+        let ranks = compute_ranks(&nf.ranks)?;
+        let fingerprint = compute_fingerprint(&[
+            &spec.created,
+            &spec.unicode,
+            &spec.cldr,
+            &nf.created,
+            &nf.unicode,
+        ]);
+        let unicode_version = version_number(&spec.unicode);
+        let cldr_version = version_number(&spec.cldr);
+        let created = spec.created.clone();
         let groups: Vec<ParsedGroup> = spec.groups.into_iter().map(ParsedGroup::from).collect();
         let group_name_to_index: HashMap<spec_json::GroupName, usize> = groups
             .iter()
@@ -47,31 +77,41 @@ impl CodePointsSpecs {
             .map(|(i, g)| (g.name.clone(), i))
             .collect();
         let valid = compute_valid(&groups, &decomp);
-        let whole_map = compute_whole_map(spec.whole_map);
+        let whole_map = compute_whole_map(spec.whole_map, &group_name_to_index)?;
+        let confusable_targets = compute_confusable_targets(spec.wholes)?;
 
         let emoji_str_list = emoji
             .iter()
             .map(|cps| utils::cps2str(cps))
             .collect::<Vec<_>>();
         let emoji_regex =
-            create_emoji_regex_pattern(emoji_str_list).expect("failed to create emoji regex");
+            create_emoji_regex_pattern(emoji_str_list).context("failed to create emoji regex")?;
 
-        Self {
+        Ok(Self {
             cm: spec.cm.into_iter().collect(),
             emoji_no_fe0f_to_pretty,
+            emoji_pretty_sorted,
             ignored: spec.ignored.into_iter().collect(),
             mapped: spec.mapped.into_iter().map(|m| (m.from, m.to)).collect(),
             nfc_check: spec.nfc_check.into_iter().collect(),
+            escape: spec.escape.into_iter().collect(),
             fenced: spec.fenced.into_iter().map(|f| (f.from, f.to)).collect(),
             valid,
             groups,
             nsm: spec.nsm.into_iter().collect(),
             nsm_max: spec.nsm_max,
             decomp,
+            ranks,
+            qc: nf.qc.into_iter().collect(),
             whole_map,
+            confusable_targets,
             group_name_to_index,
             emoji_regex,
-        }
+            fingerprint,
+            unicode_version,
+            cldr_version,
+            created,
+        })
     }
 }
 
@@ -79,11 +119,157 @@ impl Default for CodePointsSpecs {
     fn default() -> Self {
         let spec = spec_json::Spec::default();
         let nf = nf_json::Nf::default();
+        Self::new(spec, nf).expect("bundled spec is valid")
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl CodePointsSpecs {
+    /// Builds specs from a handful of tables, leaving everything else empty:
+    /// no fenced, combining or confusable code points and no NFC data.
+    /// `groups` are pairs of group name and its primary code points.
+    pub fn from_parts(
+        groups: Vec<(&str, Vec<CodePoint>)>,
+        mapped: Vec<(CodePoint, Vec<CodePoint>)>,
+        ignored: Vec<CodePoint>,
+        emoji: Vec<Vec<CodePoint>>,
+    ) -> anyhow::Result<Self> {
+        let spec = spec_json::Spec {
+            created: "test-util".to_string(),
+            unicode: String::new(),
+            cldr: String::new(),
+            emoji,
+            ignored,
+            mapped: mapped
+                .into_iter()
+                .map(|(from, to)| spec_json::Mapped { from, to })
+                .collect(),
+            fenced: vec![],
+            wholes: vec![],
+            cm: vec![],
+            nsm: vec![],
+            nsm_max: 4,
+            escape: vec![],
+            groups: groups
+                .into_iter()
+                .map(|(name, primary)| spec_json::Group {
+                    name: GroupName::from(name.to_string()),
+                    primary,
+                    secondary: vec![],
+                    cm: vec![],
+                    restricted: false,
+                })
+                .collect(),
+            nfc_check: vec![],
+            whole_map: HashMap::new(),
+        };
+        let nf = nf_json::Nf {
+            created: "test-util".to_string(),
+            unicode: String::new(),
+            ranks: vec![],
+            exclusions: vec![],
+            decomp: vec![],
+            qc: vec![],
+        };
         Self::new(spec, nf)
     }
+
+    /// Tiny deterministic spec: ASCII and Latin (`a-z`, `0-9`, `-`, `_`, `é`) and Greek (`α-ω`) groups,
+    /// `A-Z` mapped to lowercase, soft hyphen ignored and `💩` as the only emoji
+    pub fn minimal() -> Self {
+        let ascii: Vec<CodePoint> = ('a'..='z')
+            .chain('0'..='9')
+            .chain(['-', '_'])
+            .map(|c| c as CodePoint)
+            .collect();
+        let latin = ascii.iter().copied().chain([0xE9]).collect();
+        let greek = ('α'..='ω').map(|c| c as CodePoint).collect();
+        let mapped = ('A'..='Z')
+            .map(|c| (c as CodePoint, vec![c.to_ascii_lowercase() as CodePoint]))
+            .collect();
+        Self::from_parts(
+            vec![("ASCII", ascii), ("Latin", latin), ("Greek", greek)],
+            mapped,
+            vec![0xAD],
+            vec![vec![0x1F4A9, constants::CP_FE0F]],
+        )
+        .expect("minimal spec is valid")
+    }
 }
 
 impl CodePointsSpecs {
+    /// Non-panicking version of `CodePointsSpecs::default()`:
+    /// parses the bundled `spec.json` and `nf.json` and builds specs from them
+    pub fn try_default() -> anyhow::Result<Self> {
+        let spec = spec_json::Spec::try_bundled().context("failed to parse bundled spec.json")?;
+        let nf = nf_json::Nf::try_bundled().context("failed to parse bundled nf.json")?;
+        Self::new(spec, nf)
+    }
+
+    /// Builds specs from alternative `spec.json` and `nf.json` contents, e.g. a fork of the ENS spec.
+    /// Group names, `whole_map` and emoji lists are parsed the same way as the bundled data.
+    pub fn from_json_str(spec: &str, nf: &str) -> anyhow::Result<Self> {
+        let spec = serde_json::from_str(spec).context("failed to parse spec.json")?;
+        let nf = serde_json::from_str(nf).context("failed to parse nf.json")?;
+        Self::new(spec, nf)
+    }
+
+    /// Same as [`CodePointsSpecs::from_json_str`], reading the contents, e.g. from files
+    pub fn from_readers(spec: impl std::io::Read, nf: impl std::io::Read) -> anyhow::Result<Self> {
+        let spec = serde_json::from_reader(spec).context("failed to parse spec.json")?;
+        let nf = serde_json::from_reader(nf).context("failed to parse nf.json")?;
+        Self::new(spec, nf)
+    }
+
+    /// Normalizes a name given as code points, returning normalized code points.
+    /// Same as [`crate::EnsNameNormalizer::normalize`] with default options.
+    pub fn normalize_cps(&self, cps: &[CodePoint]) -> Result<Vec<CodePoint>, ProcessError> {
+        let input = cps
+            .iter()
+            .enumerate()
+            .map(|(index, cp)| {
+                char::from_u32(*cp).ok_or_else(|| {
+                    ProcessError::DisallowedSequence(DisallowedSequence::Invalid {
+                        sequence: format!("U+{cp:04X}"),
+                        index,
+                    })
+                })
+            })
+            .collect::<Result<String, _>>()?;
+        let processed =
+            crate::normalizer::process_with(&input, self, &ValidationOptions::default())?;
+        Ok(join_labels_cps(&processed.labels))
+    }
+
+    /// Identifier of the loaded spec version, derived from the
+    /// creation timestamps and Unicode versions of `spec.json` and `nf.json`
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Unicode version the spec was built against, e.g. `16.0.0`
+    pub fn unicode_version(&self) -> &str {
+        &self.unicode_version
+    }
+
+    /// CLDR version the spec was built against, e.g. `45`
+    pub fn cldr_version(&self) -> &str {
+        &self.cldr_version
+    }
+
+    /// Creation timestamp of `spec.json`, e.g. `2024-09-13T06:42:44.238Z`
+    pub fn created(&self) -> &str {
+        &self.created
+    }
+
+    /// Mixes custom additions into the fingerprint, see [`super::CodePointsSpecsBuilder`]
+    pub(super) fn extend_fingerprint(&mut self, parts: &[String]) {
+        let parts = std::iter::once(self.fingerprint.as_str())
+            .chain(parts.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        self.fingerprint = compute_fingerprint(&parts);
+    }
+
     pub fn get_mapping(&self, cp: CodePoint) -> Option<&Vec<CodePoint>> {
         self.mapped.get(&cp)
     }
@@ -104,6 +290,40 @@ impl CodePointsSpecs {
         cps.iter().any(|cp| self.nfc_check.contains(cp))
     }
 
+    /// NFC quick check: returns true if the code points are certainly in NFC,
+    /// i.e. none of them is in the `qc` set of `nf.json` and combining marks are canonically ordered.
+    /// `false` means full composition is needed to tell.
+    pub fn nfc_quick_check(&self, cps: &[CodePoint]) -> bool {
+        let mut last_rank = 0;
+        for cp in cps {
+            if self.qc.contains(cp) {
+                return false;
+            }
+            let rank = self.combining_rank(*cp);
+            if rank != 0 && last_rank > rank {
+                return false;
+            }
+            last_rank = rank;
+        }
+        true
+    }
+
+    /// Returns true if the code point alone is an ENS emoji displayed as emoji by default,
+    /// i.e. it has `Emoji_Presentation`, e.g. `💩`
+    pub fn has_emoji_presentation(&self, cp: CodePoint) -> bool {
+        self.is_single_emoji(cp) && presentation::is_emoji_presentation(cp)
+    }
+
+    /// Returns true if the code point alone is an ENS emoji displayed as text by default,
+    /// i.e. it needs `FE0F` to be rendered as emoji, e.g. `❤`
+    pub fn has_text_presentation(&self, cp: CodePoint) -> bool {
+        self.is_single_emoji(cp) && !presentation::is_emoji_presentation(cp)
+    }
+
+    fn is_single_emoji(&self, cp: CodePoint) -> bool {
+        self.emoji_no_fe0f_to_pretty.contains_key([cp].as_slice())
+    }
+
     pub fn cps_emoji_no_fe0f_to_pretty(&self, cps: &[CodePoint]) -> Option<&Vec<CodePoint>> {
         self.emoji_no_fe0f_to_pretty.get(cps)
     }
@@ -120,14 +340,54 @@ impl CodePointsSpecs {
         self.ignored.contains(&cp)
     }
 
+    /// Returns true if the code point should always be rendered escaped, e.g. as `{200C}`,
+    /// because it is invisible or otherwise confusing when displayed
+    pub fn needs_escape(&self, cp: CodePoint) -> bool {
+        self.escape.contains(&cp)
+    }
+
+    /// Same as [`CodePointsSpecs::needs_escape`], named after the reference implementation.
+    /// Used by [`crate::safe_display`].
+    pub fn should_escape(&self, cp: CodePoint) -> bool {
+        self.needs_escape(cp)
+    }
+
+    pub fn is_mapped(&self, cp: CodePoint) -> bool {
+        self.mapped.contains_key(&cp)
+    }
+
     pub fn is_stop(&self, cp: CodePoint) -> bool {
         cp == constants::CP_STOP
     }
 
+    /// Returns true if the raw input contains a label separator
+    pub fn contains_stop(&self, input: &str) -> bool {
+        input.chars().any(|c| self.is_stop(c as CodePoint))
+    }
+
     pub fn is_fenced(&self, cp: CodePoint) -> bool {
         self.fenced.contains_key(&cp)
     }
 
+    /// Returns the descriptive name of a fenced code point, e.g. `"middle dot"`
+    pub fn fenced_info(&self, cp: CodePoint) -> Option<&str> {
+        self.fenced.get(&cp).map(String::as_str)
+    }
+
+    /// Every supported emoji sequence in its fully-qualified form, i.e. with `FE0F`
+    /// as [`crate::EnsNameNormalizer::beautify`] writes it, ordered by code points
+    pub fn emoji_iter(&self) -> impl Iterator<Item = &Vec<CodePoint>> {
+        self.emoji_pretty_sorted.iter()
+    }
+
+    /// Every fenced code point with its descriptive name, ordered by code point
+    pub fn all_fenced(&self) -> impl Iterator<Item = (CodePoint, &str)> {
+        self.fenced
+            .iter()
+            .map(|(cp, name)| (*cp, name.as_str()))
+            .sorted_by_key(|(cp, _)| *cp)
+    }
+
     pub fn is_cm(&self, cp: CodePoint) -> bool {
         self.cm.contains(&cp)
     }
@@ -141,6 +401,44 @@ impl CodePointsSpecs {
             .filter(|group| cps.iter().all(|cp| group.contains_cp(*cp)))
     }
 
+    /// Resolves the group of the given code points, i.e. the first group containing all of them.
+    /// Fails with the same `Confused` error as validation of a label made of these code points
+    /// if no such group exists.
+    pub fn resolve_group(&self, cps: &[CodePoint]) -> Result<&ParsedGroup, ProcessError> {
+        self.resolve_group_described(cps, |cp| self.describe_cp(cp))
+    }
+
+    /// Same as [`CodePointsSpecs::resolve_group`], listing the code points of the error with `describe`
+    pub(crate) fn resolve_group_described(
+        &self,
+        cps: &[CodePoint],
+        describe: impl Fn(CodePoint) -> String,
+    ) -> Result<&ParsedGroup, ProcessError> {
+        let unique_cps = cps.iter().cloned().unique().collect::<Vec<_>>();
+        self.groups
+            .iter()
+            .find(|group| group.contains_all_cps(&unique_cps))
+            .ok_or_else(|| {
+                ProcessError::Confused(format!(
+                    "no group found for [{}]",
+                    unique_cps.iter().map(|cp| describe(*cp)).join(", ")
+                ))
+            })
+    }
+
+    /// Name of the Unicode block containing the code point, e.g. `"Arabic"`
+    pub fn block_name(&self, cp: CodePoint) -> Option<&'static str> {
+        blocks::block_name(cp)
+    }
+
+    /// Code point with its block for diagnostics, e.g. `"U+0627 (Arabic)"`
+    pub fn describe_cp(&self, cp: CodePoint) -> String {
+        match self.block_name(cp) {
+            Some(block) => format!("U+{cp:04X} ({block})"),
+            None => format!("U+{cp:04X}"),
+        }
+    }
+
     pub fn is_nsm(&self, cp: CodePoint) -> bool {
         self.nsm.contains(&cp)
     }
@@ -153,15 +451,98 @@ impl CodePointsSpecs {
         self.decomp.get(&cp)
     }
 
+    /// Position of the code point's canonical combining class among all non-zero classes,
+    /// starting from 1. Returns 0 for starters, same as combining class 0.
+    pub fn combining_rank(&self, cp: CodePoint) -> u8 {
+        self.ranks.get(&cp).copied().unwrap_or(0)
+    }
+
+    /// Canonical combining class of the code point, e.g. `230` for `U+0301`.
+    /// Code points missing from `nf.json` ranks are reported as class `0`.
+    pub fn combining_class(&self, cp: CodePoint) -> u8 {
+        match (self.combining_rank(cp), char::from_u32(cp)) {
+            (0, _) | (_, None) => 0,
+            (_, Some(c)) => unicode_normalization::char::canonical_combining_class(c),
+        }
+    }
+
+    /// Confusable skeleton of the code point, i.e. the representative of the set of
+    /// code points that look alike, e.g. Cyrillic `а` and Latin `a` share the skeleton `a`
+    pub fn confusable_target(&self, cp: CodePoint) -> Option<&[CodePoint]> {
+        self.confusable_targets.get(&cp).map(Vec::as_slice)
+    }
+
     pub fn whole_map(&self, cp: CodePoint) -> Option<&ParsedWholeValue> {
         self.whole_map.get(&cp)
     }
 
+    /// Classifies a single character as it would be treated inside a label.
+    /// Emoji sequences and NFC depend on the neighbouring characters and are not considered.
+    /// Useful for per-keystroke validation.
+    pub fn normalize_char(&self, c: char) -> CharResult {
+        if self.is_single_emoji(c as CodePoint) {
+            return CharResult::Emoji;
+        }
+        match process_one_cp(c as CodePoint, self) {
+            EnsNameToken::Valid(_) => CharResult::Valid(c),
+            EnsNameToken::Mapped(mapped) => CharResult::Mapped(utils::cps2str(&mapped.cps)),
+            EnsNameToken::Ignored(_) => CharResult::Ignored,
+            EnsNameToken::Stop(_) => CharResult::Stop,
+            EnsNameToken::Disallowed(_) | EnsNameToken::Nfc(_) | EnsNameToken::Emoji(_) => {
+                CharResult::Disallowed
+            }
+        }
+    }
+
+    /// Number of emoji sequences in the spec
+    pub fn emoji_count(&self) -> usize {
+        self.emoji_no_fe0f_to_pretty.len()
+    }
+
+    /// Length in code points of the longest emoji sequence, including `FE0F`
+    pub fn max_emoji_cp_len(&self) -> usize {
+        self.emoji_no_fe0f_to_pretty
+            .values()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns sizes of the loaded tables, useful to confirm the spec was loaded completely
+    pub fn stats(&self) -> SpecStats {
+        SpecStats {
+            groups: self.groups.len(),
+            valid: self.valid.len(),
+            mapped: self.mapped.len(),
+            emoji: self.emoji_no_fe0f_to_pretty.len(),
+            fenced: self.fenced.len(),
+            nsm: self.nsm.len(),
+            nsm_max: self.nsm_max,
+        }
+    }
+
     pub fn group_by_name(&self, name: impl Into<GroupName>) -> Option<&ParsedGroup> {
         self.group_name_to_index
             .get(&name.into())
             .and_then(|i| self.groups.get(*i))
     }
+
+    /// Primary code points of the given group, `None` if the group does not exist
+    pub fn group_primary(&self, name: impl Into<GroupName>) -> Option<&HashSet<CodePoint>> {
+        self.group_by_name(name).map(|group| &group.primary)
+    }
+
+    /// Secondary code points of the given group, `None` if the group does not exist
+    pub fn group_secondary(&self, name: impl Into<GroupName>) -> Option<&HashSet<CodePoint>> {
+        self.group_by_name(name).map(|group| &group.secondary)
+    }
+
+    /// Returns a validator for labels known to belong to the given group,
+    /// e.g. a bulk import of Cyrillic names. `None` if the group does not exist.
+    pub fn validator_for_group(&self, name: impl Into<GroupName>) -> Option<LabelValidator<'_>> {
+        self.group_by_name(name)
+            .map(|group| LabelValidator::new(self, group))
+    }
 }
 
 fn compute_valid(
@@ -181,13 +562,83 @@ fn compute_valid(
     valid
 }
 
-fn compute_whole_map(whole_map: HashMap<String, spec_json::WholeValue>) -> ParsedWholeMap {
+/// Drops the release date from versions like `16.0.0 (2024-09-10T20:47:54.200Z)`
+fn version_number(version: &str) -> String {
+    version
+        .split_once(" (")
+        .map_or(version, |(number, _)| number)
+        .to_string()
+}
+
+/// 64-bit FNV-1a hash of the parts, stable across platforms and compiler versions
+fn compute_fingerprint(parts: &[&str]) -> String {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    let hash = parts
+        .iter()
+        .flat_map(|part| part.bytes().chain(std::iter::once(0)))
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+    format!("{hash:016x}")
+}
+
+fn compute_ranks(ranks: &[Vec<CodePoint>]) -> anyhow::Result<HashMap<CodePoint, u8>> {
+    let mut result = HashMap::new();
+    for (i, cps) in ranks.iter().enumerate() {
+        let rank = u8::try_from(i + 1).context("too many combining class ranks")?;
+        result.extend(cps.iter().map(|cp| (*cp, rank)));
+    }
+    Ok(result)
+}
+
+/// Parses `whole_map`, checking what whole-script confusable detection relies on:
+/// every whole code point has confused groups in `M` and every such group exists
+fn compute_whole_map(
+    whole_map: HashMap<String, spec_json::WholeValue>,
+    group_name_to_index: &HashMap<GroupName, usize>,
+) -> anyhow::Result<ParsedWholeMap> {
     whole_map
         .into_iter()
-        .map(|(k, v)| (k.parse::<CodePoint>().unwrap(), v.try_into().unwrap()))
+        .map(|(k, v)| {
+            let cp = k
+                .parse::<CodePoint>()
+                .with_context(|| format!("invalid code point in whole_map: {k}"))?;
+            let value = v.try_into()?;
+            if let ParsedWholeValue::WholeObject(whole) = &value {
+                let groups = whole
+                    .m
+                    .get(&cp)
+                    .with_context(|| format!("no confused groups for {cp} in whole_map"))?;
+                if let Some(name) = groups.iter().find(|name| {
+                    !group_name_to_index.contains_key(&GroupName::from((*name).clone()))
+                }) {
+                    anyhow::bail!("unknown group {name} for {cp} in whole_map");
+                }
+            }
+            Ok((cp, value))
+        })
         .collect()
 }
 
+fn compute_confusable_targets(
+    wholes: Vec<spec_json::Whole>,
+) -> anyhow::Result<HashMap<CodePoint, Vec<CodePoint>>> {
+    let mut targets = HashMap::new();
+    for whole in wholes {
+        let target = whole
+            .target
+            .split_whitespace()
+            .map(|hex| CodePoint::from_str_radix(hex, 16))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("invalid confusable target: {}", whole.target))?;
+        for cp in whole.valid.into_iter().chain(whole.confused) {
+            targets.insert(cp, target.clone());
+        }
+    }
+    Ok(targets)
+}
+
 fn create_emoji_regex_pattern(emojis: Vec<impl AsRef<str>>) -> Result<Regex, regex::Error> {
     let fe0f = regex::escape(constants::STR_FE0F);
 
@@ -197,6 +648,11 @@ fn create_emoji_regex_pattern(emojis: Vec<impl AsRef<str>>) -> Result<Regex, reg
     // Order emojis to match the longest ones first
     let order = |emoji: &str| emoji.replace(constants::STR_FE0F, "").len();
 
+    if emojis.is_empty() {
+        // an empty alternation would match everywhere, use a pattern that never matches
+        return regex::Regex::new(r"[^\s\S]");
+    }
+
     let mut sorted_emojis = emojis;
     sorted_emojis.sort_by_key(|b| std::cmp::Reverse(order(b.as_ref())));
 
@@ -230,6 +686,167 @@ mod tests {
         assert_eq!(mapped, Some(&expected));
     }
 
+    #[rstest]
+    fn test_versions(specs: &CodePointsSpecs) {
+        assert_eq!(specs.unicode_version(), "16.0.0");
+        assert_eq!(specs.cldr_version(), "45");
+        assert_eq!(specs.created(), "2024-09-13T06:42:44.238Z");
+    }
+
+    #[rstest]
+    #[case::with_date("16.0.0 (2024-09-10T20:47:54.200Z)", "16.0.0")]
+    #[case::plain("15.1.0", "15.1.0")]
+    #[case::empty("", "")]
+    fn test_version_number(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(version_number(input), expected);
+    }
+
+    const SPEC_JSON: &str = include_str!("../static_data/spec.json");
+    const NF_JSON: &str = include_str!("../static_data/nf.json");
+
+    #[rstest]
+    fn test_from_json_str_bundled(specs: &CodePointsSpecs) {
+        let loaded = CodePointsSpecs::from_json_str(SPEC_JSON, NF_JSON).unwrap();
+        assert_eq!(loaded.fingerprint(), specs.fingerprint());
+        let read = CodePointsSpecs::from_readers(SPEC_JSON.as_bytes(), NF_JSON.as_bytes()).unwrap();
+        assert_eq!(read.fingerprint(), specs.fingerprint());
+    }
+
+    #[test]
+    fn test_from_json_str_custom() {
+        let mut spec: serde_json::Value = serde_json::from_str(SPEC_JSON).unwrap();
+        spec["emoji"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!([0xE000]));
+        let custom = CodePointsSpecs::from_json_str(&spec.to_string(), NF_JSON).unwrap();
+        assert!(custom.cps_is_emoji(&[0xE000]));
+        assert!(!CodePointsSpecs::default().cps_is_emoji(&[0xE000]));
+    }
+
+    #[test]
+    fn test_from_json_str_other_rank_count() {
+        let mut nf: serde_json::Value = serde_json::from_str(NF_JSON).unwrap();
+        nf["ranks"].as_array_mut().unwrap().pop();
+        let custom = CodePointsSpecs::from_json_str(SPEC_JSON, &nf.to_string()).unwrap();
+        assert_eq!(custom.combining_class(0x301), 230);
+    }
+
+    #[rstest]
+    #[case::invalid_spec("{}", NF_JSON, "failed to parse spec.json")]
+    #[case::invalid_nf(SPEC_JSON, "[", "failed to parse nf.json")]
+    fn test_from_json_str_invalid(#[case] spec: &str, #[case] nf: &str, #[case] expected: &str) {
+        let err = CodePointsSpecs::from_json_str(spec, nf).err().unwrap();
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[test]
+    fn test_from_json_str_without_wholes() {
+        let mut spec: serde_json::Value = serde_json::from_str(SPEC_JSON).unwrap();
+        spec.as_object_mut().unwrap().remove("wholes");
+        let err = CodePointsSpecs::from_json_str(&spec.to_string(), NF_JSON)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "failed to parse spec.json");
+    }
+
+    #[rstest]
+    #[case::missing_m_entry(
+        |m: &mut serde_json::Value| {
+            m.as_object_mut().unwrap().remove("769");
+        },
+        "no confused groups for 769 in whole_map"
+    )]
+    #[case::unknown_group(
+        |m: &mut serde_json::Value| m["769"] = serde_json::json!(["Hebrew", "Klingon"]),
+        "unknown group Klingon for 769 in whole_map"
+    )]
+    fn test_from_json_str_inconsistent_whole_map(
+        #[case] edit: fn(&mut serde_json::Value),
+        #[case] expected: &str,
+    ) {
+        let mut spec: serde_json::Value = serde_json::from_str(SPEC_JSON).unwrap();
+        edit(&mut spec["whole_map"]["769"]["M"]);
+        let err = CodePointsSpecs::from_json_str(&spec.to_string(), NF_JSON)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_emoji_iter(specs: &CodePointsSpecs) {
+        let emoji = specs.emoji_iter().collect::<Vec<_>>();
+        assert_eq!(emoji.len(), specs.emoji_no_fe0f_to_pretty.len());
+        assert!(emoji.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(emoji.contains(&&vec![0x2764, constants::CP_FE0F, 0x200D, 0x1F525]));
+        // pretty form keeps FE0F
+        assert!(emoji.contains(&&vec![0x1F4A9, constants::CP_FE0F]));
+        assert!(!emoji.contains(&&vec![0x1F4A9]));
+    }
+
+    #[rstest]
+    #[case::starter('a', 0)]
+    #[case::overlay('\u{334}', 1)]
+    #[case::hebrew_sheva('\u{5B0}', 10)]
+    #[case::below('\u{316}', 220)]
+    #[case::acute('\u{301}', 230)]
+    #[case::iota_subscript('\u{345}', 240)]
+    fn test_combining_class(#[case] input: char, #[case] expected: u8, specs: &CodePointsSpecs) {
+        assert_eq!(specs.combining_class(input as CodePoint), expected);
+    }
+
+    #[rstest]
+    fn test_combining_class_agrees_with_unicode(specs: &CodePointsSpecs) {
+        // nf.json only ranks code points relevant to ENS, e.g. U+1ACF is missing
+        let ranked = (0..=0x10FFFF)
+            .filter_map(char::from_u32)
+            .filter(|c| specs.combining_rank(*c as CodePoint) != 0);
+        for c in ranked {
+            assert_eq!(
+                specs.combining_class(c as CodePoint),
+                unicode_normalization::char::canonical_combining_class(c),
+                "U+{:04X}",
+                c as u32
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::latin('a', Some(vec![0x61]))]
+    #[case::cyrillic('а', Some(vec![0x61]))]
+    #[case::multi_cp_target('\u{901}', Some(vec![0x306, 0x307]))]
+    #[case::not_confusable('ж', None)]
+    fn test_confusable_target(
+        #[case] input: char,
+        #[case] expected: Option<Vec<CodePoint>>,
+        specs: &CodePointsSpecs,
+    ) {
+        assert_eq!(
+            specs.confusable_target(input as CodePoint),
+            expected.as_deref()
+        );
+    }
+
+    #[rstest]
+    #[case::zwnj('\u{200C}', true)]
+    #[case::soft_hyphen('\u{AD}', true)]
+    #[case::nul('\0', true)]
+    #[case::letter('a', false)]
+    #[case::combining_mark('\u{301}', false)]
+    fn test_needs_escape(#[case] input: char, #[case] expected: bool, specs: &CodePointsSpecs) {
+        assert_eq!(specs.needs_escape(input as CodePoint), expected);
+    }
+
+    #[rstest]
+    #[case::uppercase('A', true)]
+    #[case::fullwidth('ａ', true)]
+    #[case::valid('a', false)]
+    #[case::ignored('\u{AD}', false)]
+    #[case::stop('.', false)]
+    fn test_is_mapped(#[case] input: char, #[case] expected: bool, specs: &CodePointsSpecs) {
+        assert_eq!(specs.is_mapped(input as CodePoint), expected);
+    }
+
     #[rstest]
     #[case::slash("⁄")]
     fn test_fenced(#[case] fence: &str, specs: &CodePointsSpecs) {
@@ -241,6 +858,17 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_all_fenced(specs: &CodePointsSpecs) {
+        let fenced = specs.all_fenced().collect::<Vec<_>>();
+        assert_eq!(fenced.len(), specs.stats().fenced);
+        assert!(fenced.is_sorted_by_key(|(cp, _)| *cp));
+        assert!(fenced.contains(&(0x2044, "fraction slash")));
+        for (cp, name) in fenced {
+            assert_eq!(specs.fenced_info(cp), Some(name));
+        }
+    }
+
     #[rstest]
     #[case::string("hello😀", vec![("😀", 5, 9)])]
     #[case::man_technologist("👨‍💻", vec![("👨‍💻", 0, 11)])]
@@ -270,6 +898,217 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case::ascii("hello", Ok("Latin"))]
+    #[case::latin("café", Ok("Latin"))]
+    #[case::cyrillic("привет", Ok("Cyrillic"))]
+    #[case::mixed("helloпривет", Err(()))]
+    fn test_resolve_group(
+        #[case] input: &str,
+        #[case] expected: Result<&str, ()>,
+        specs: &CodePointsSpecs,
+    ) {
+        let cps = utils::str2cps(input);
+        let result = specs
+            .resolve_group(&cps)
+            .map(|group| group.name.to_string())
+            .map_err(|_| ());
+        assert_eq!(result.as_deref(), expected.as_deref());
+    }
+
+    #[rstest]
+    fn test_stats(specs: &CodePointsSpecs) {
+        let stats = specs.stats();
+        assert_eq!(stats.groups, 163);
+        assert_eq!(stats.emoji, 3763);
+        assert_eq!(stats.fenced, 3);
+        assert_eq!(stats.nsm, 1095);
+        assert_eq!(stats.nsm_max, 4);
+        assert!(stats.valid > stats.mapped);
+    }
+
+    #[rstest]
+    #[case::valid('a', CharResult::Valid('a'))]
+    #[case::mapped('A', CharResult::Mapped("a".to_string()))]
+    #[case::mapped_to_many('Ⅵ', CharResult::Mapped("vi".to_string()))]
+    #[case::ignored('\u{AD}', CharResult::Ignored)]
+    #[case::stop('.', CharResult::Stop)]
+    #[case::disallowed('!', CharResult::Disallowed)]
+    #[case::emoji('💩', CharResult::Emoji)]
+    fn test_normalize_char(#[case] c: char, #[case] expected: CharResult, specs: &CodePointsSpecs) {
+        assert_eq!(specs.normalize_char(c), expected);
+    }
+
+    #[rstest]
+    fn test_emoji_sizes(specs: &CodePointsSpecs) {
+        assert_eq!(specs.emoji_count(), specs.stats().emoji);
+        assert_eq!(specs.max_emoji_cp_len(), 10);
+        assert!(specs.max_emoji_cp_len() <= constants::MAX_EMOJI_LEN);
+    }
+
+    #[rstest]
+    #[case::ascii("vitalik", true)]
+    #[case::composed("caf\u{e9}", true)]
+    #[case::decomposed("cafe\u{301}", false)]
+    #[case::hangul_jamo("\u{1100}\u{1161}", false)]
+    #[case::marks_unordered("\u{628}\u{650}\u{64e}", false)]
+    #[case::cyrillic("привет", true)]
+    fn test_nfc_quick_check(#[case] input: &str, #[case] expected: bool, specs: &CodePointsSpecs) {
+        let cps = utils::str2cps(input);
+        assert_eq!(specs.nfc_quick_check(&cps), expected);
+    }
+
+    #[rstest]
+    fn test_nfc_quick_check_agrees_with_nfc(specs: &CodePointsSpecs) {
+        for cp in 0..0x3000 {
+            let Some(c) = char::from_u32(cp) else {
+                continue;
+            };
+            for input in [c.to_string(), format!("a{c}"), format!("{c}a")] {
+                if specs.nfc_quick_check(&utils::str2cps(&input)) {
+                    assert_eq!(utils::nfc(&input), input, "{cp:x}");
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::poop(0x1F4A9, true, false)]
+    #[case::heart(0x2764, false, true)]
+    #[case::trade_mark(0x2122, false, false)]
+    #[case::letter(0x61, false, false)]
+    fn test_presentation(
+        #[case] cp: CodePoint,
+        #[case] emoji: bool,
+        #[case] text: bool,
+        specs: &CodePointsSpecs,
+    ) {
+        assert_eq!(specs.has_emoji_presentation(cp), emoji);
+        assert_eq!(specs.has_text_presentation(cp), text);
+    }
+
+    #[rstest]
+    #[case::ascii(0x61, Some("Basic Latin"))]
+    #[case::block_start(0x600, Some("Arabic"))]
+    #[case::block_end(0x6FF, Some("Arabic"))]
+    #[case::emoji(0x1F4A9, Some("Miscellaneous Symbols and Pictographs"))]
+    #[case::unicode_16(0x105C0, Some("Todhri"))]
+    #[case::no_block(0x2FE0, None)]
+    fn test_block_name(
+        #[case] cp: CodePoint,
+        #[case] expected: Option<&str>,
+        specs: &CodePointsSpecs,
+    ) {
+        assert_eq!(specs.block_name(cp), expected);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[rstest]
+    #[case::ascii("hello", Some("hello"))]
+    #[case::mapped("HeLLo", Some("hello"))]
+    #[case::ignored("he\u{AD}llo", Some("hello"))]
+    #[case::latin("café", Some("café"))]
+    #[case::greek("αβγ", Some("αβγ"))]
+    #[case::emoji("💩.eth", Some("💩.eth"))]
+    #[case::mixed_groups("éα", None)]
+    #[case::not_in_spec("привет", None)]
+    fn test_minimal(#[case] input: &str, #[case] expected: Option<&str>) {
+        let normalizer = crate::EnsNameNormalizer::new(CodePointsSpecs::minimal());
+        let actual = normalizer.normalize(input).ok();
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[rstest]
+    fn test_from_parts_without_emoji() {
+        let specs =
+            CodePointsSpecs::from_parts(vec![("ASCII", vec![0x61])], vec![], vec![], vec![])
+                .unwrap();
+        let normalizer = crate::EnsNameNormalizer::new(specs);
+        assert_eq!(normalizer.normalize("a.a"), Ok("a.a".to_string()));
+        assert!(normalizer.normalize("b").is_err());
+    }
+
+    #[rstest]
+    #[case::name("vitalik.eth", true)]
+    #[case::label("vitalik", false)]
+    #[case::only_stop(".", true)]
+    #[case::fullwidth_stop("vitalik\u{FF0E}eth", false)]
+    #[case::empty("", false)]
+    fn test_contains_stop(#[case] input: &str, #[case] expected: bool, specs: &CodePointsSpecs) {
+        assert_eq!(specs.contains_stop(input), expected);
+    }
+
+    #[rstest]
+    #[case::primary("Latin", 'a', Some(Membership::Primary))]
+    #[case::secondary("Latin", 'π', Some(Membership::Secondary))]
+    #[case::absent("Latin", 'б', None)]
+    #[case::cyrillic("Cyrillic", 'б', Some(Membership::Primary))]
+    fn test_group_membership(
+        #[case] group: &str,
+        #[case] c: char,
+        #[case] expected: Option<Membership>,
+        specs: &CodePointsSpecs,
+    ) {
+        let group = specs.group_by_name(group.to_string()).unwrap();
+        assert_eq!(group.membership(c as CodePoint), expected);
+    }
+
+    #[rstest]
+    fn test_group_primary_secondary(specs: &CodePointsSpecs) {
+        let primary = specs.group_primary("Latin".to_string()).unwrap();
+        let secondary = specs.group_secondary("Latin".to_string()).unwrap();
+        assert!(primary.contains(&('a' as CodePoint)));
+        assert!(!primary.contains(&('π' as CodePoint)));
+        assert!(secondary.contains(&('π' as CodePoint)));
+        assert!(specs.group_primary("Klingon".to_string()).is_none());
+        assert!(specs.group_secondary("Klingon".to_string()).is_none());
+    }
+
+    #[rstest]
+    #[case::mapped("Vitalik.eth", Ok("vitalik.eth"))]
+    #[case::emoji("💩\u{FE0F}.eth", Ok("💩.eth"))]
+    #[case::empty("", Ok(""))]
+    #[case::invalid("vitalik..eth", Err(()))]
+    fn test_normalize_cps(
+        #[case] input: &str,
+        #[case] expected: Result<&str, ()>,
+        specs: &CodePointsSpecs,
+    ) {
+        let actual = specs.normalize_cps(&utils::str2cps(input)).map_err(|_| ());
+        assert_eq!(actual, expected.map(utils::str2cps));
+    }
+
+    #[rstest]
+    fn test_normalize_cps_surrogate(specs: &CodePointsSpecs) {
+        assert_eq!(
+            specs.normalize_cps(&[0x61, 0xD800]),
+            Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::Invalid {
+                    sequence: "U+D800".to_string(),
+                    index: 1
+                }
+            ))
+        );
+    }
+
+    #[rstest]
+    fn test_normalize_cps_control_character(specs: &CodePointsSpecs) {
+        assert_eq!(
+            specs.normalize_cps(&[0x61, 0]),
+            Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::ControlCharacter(0)
+            ))
+        );
+    }
+
+    #[rstest]
+    fn test_try_default(specs: &CodePointsSpecs) {
+        let loaded = CodePointsSpecs::try_default().expect("bundled spec is valid");
+        assert_eq!(loaded.fingerprint(), specs.fingerprint());
+        assert_eq!(loaded.stats(), specs.stats());
+    }
+
     #[rstest]
     #[case(&[82])]
     fn test_not_valid(#[case] cps: &[CodePoint], specs: &CodePointsSpecs) {
@@ -10,6 +10,7 @@ pub struct ParsedGroup {
     pub secondary: HashSet<CodePoint>,
     pub primary_plus_secondary: HashSet<CodePoint>,
     pub cm_absent: bool,
+    pub restricted: bool,
 }
 
 impl From<spec_json::Group> for ParsedGroup {
@@ -25,11 +26,30 @@ impl From<spec_json::Group> for ParsedGroup {
                 .chain(g.secondary.clone())
                 .collect(),
             cm_absent: g.cm.is_empty(),
+            restricted: g.restricted,
         }
     }
 }
 
+/// Which set of a group a code point belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Membership {
+    Primary,
+    Secondary,
+}
+
 impl ParsedGroup {
+    /// Returns whether the code point is in the primary or the secondary set of the group
+    pub fn membership(&self, cp: CodePoint) -> Option<Membership> {
+        if self.primary.contains(&cp) {
+            Some(Membership::Primary)
+        } else if self.secondary.contains(&cp) {
+            Some(Membership::Secondary)
+        } else {
+            None
+        }
+    }
+
     pub fn contains_cp(&self, cp: CodePoint) -> bool {
         self.primary_plus_secondary.contains(&cp)
     }
@@ -39,6 +59,35 @@ impl ParsedGroup {
     }
 }
 
+/// How a single character is treated inside a label, see [`crate::CodePointsSpecs::normalize_char`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharResult {
+    /// Kept as is
+    Valid(char),
+    /// Replaced by one or more characters
+    Mapped(String),
+    /// Removed from the output
+    Ignored,
+    /// Not allowed in names
+    Disallowed,
+    /// Label separator
+    Stop,
+    /// Emoji on its own, e.g. `💩`
+    Emoji,
+}
+
+/// Sizes of the tables loaded into [`crate::CodePointsSpecs`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecStats {
+    pub groups: usize,
+    pub valid: usize,
+    pub mapped: usize,
+    pub emoji: usize,
+    pub fenced: usize,
+    pub nsm: usize,
+    pub nsm_max: u32,
+}
+
 pub type ParsedWholeMap = HashMap<CodePoint, ParsedWholeValue>;
 
 pub enum ParsedWholeValue {
@@ -0,0 +1,180 @@
+use crate::{utils, EnsNameToken, ProcessedName};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Transform that made two different inputs normalize to the same text.
+/// `a` and `b` are the diverging parts of the first and the second input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollisionReason {
+    /// At least one side was mapped, e.g. `A` and `ａ` both map to `a`
+    Mapped { a: String, b: String },
+    /// Sides differ in composition, e.g. precomposed `é` and `e` followed by `U+0301`
+    Nfc { a: String, b: String },
+    /// Emoji differ in `FE0F` presentation selectors
+    Emoji { a: String, b: String },
+    /// Sides differ in ignored code points, e.g. a soft hyphen
+    Ignored { a: String, b: String },
+}
+
+/// Smallest piece of a token stream: input text and the number of output code points.
+/// Valid tokens are split into single code points, so both streams can be aligned.
+struct Unit<'a> {
+    token: &'a EnsNameToken,
+    input: String,
+    output_len: usize,
+}
+
+#[derive(Default)]
+struct Segment<'a> {
+    units: Vec<Unit<'a>>,
+}
+
+impl Segment<'_> {
+    fn text(&self) -> String {
+        self.units
+            .iter()
+            .filter(|unit| !unit.token.is_ignored())
+            .map(|unit| unit.input.as_str())
+            .collect()
+    }
+
+    fn ignored(&self) -> String {
+        self.units
+            .iter()
+            .filter(|unit| unit.token.is_ignored())
+            .map(|unit| unit.input.as_str())
+            .collect()
+    }
+
+    fn has(&self, f: impl Fn(&EnsNameToken) -> bool) -> bool {
+        self.units.iter().any(|unit| f(unit.token))
+    }
+}
+
+/// Aligns token streams of two names with the same normalized form and reports
+/// every transform that differs between them, keyed by the code point index in the normalized name
+pub(crate) fn explain_collision(
+    a: &ProcessedName,
+    b: &ProcessedName,
+) -> Vec<(usize, CollisionReason)> {
+    let units_a = units(&a.tokenized.tokens);
+    let units_b = units(&b.tokenized.tokens);
+    let boundaries = &boundaries(&units_a) & &boundaries(&units_b);
+    let mut segments_a = segments(units_a, &boundaries);
+    let mut segments_b = segments(units_b, &boundaries);
+    let starts = segments_a
+        .keys()
+        .chain(segments_b.keys())
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let mut reasons = Vec::new();
+    for start in starts {
+        let a = segments_a.remove(&start).unwrap_or_default();
+        let b = segments_b.remove(&start).unwrap_or_default();
+        let (ignored_a, ignored_b) = (a.ignored(), b.ignored());
+        if ignored_a != ignored_b {
+            reasons.push((
+                start,
+                CollisionReason::Ignored {
+                    a: ignored_a,
+                    b: ignored_b,
+                },
+            ));
+        }
+        let (text_a, text_b) = (a.text(), b.text());
+        if text_a != text_b {
+            let reason = if a.has(EnsNameToken::is_nfc) || b.has(EnsNameToken::is_nfc) {
+                CollisionReason::Nfc {
+                    a: text_a,
+                    b: text_b,
+                }
+            } else if a.has(EnsNameToken::is_emoji) || b.has(EnsNameToken::is_emoji) {
+                CollisionReason::Emoji {
+                    a: text_a,
+                    b: text_b,
+                }
+            } else {
+                CollisionReason::Mapped {
+                    a: text_a,
+                    b: text_b,
+                }
+            };
+            reasons.push((start, reason));
+        }
+    }
+    reasons
+}
+
+fn units(tokens: &[EnsNameToken]) -> Vec<Unit<'_>> {
+    let mut units = Vec::new();
+    for token in tokens {
+        match token {
+            EnsNameToken::Valid(valid) => units.extend(valid.cps.iter().map(|cp| Unit {
+                token,
+                input: utils::cp2str(*cp),
+                output_len: 1,
+            })),
+            EnsNameToken::Mapped(mapped) => units.push(Unit {
+                token,
+                input: utils::cp2str(mapped.cp),
+                output_len: mapped.cps.len(),
+            }),
+            EnsNameToken::Nfc(nfc) => units.push(Unit {
+                token,
+                input: utils::cps2str(&nfc.input),
+                output_len: nfc.cps.len(),
+            }),
+            EnsNameToken::Emoji(emoji) => units.push(Unit {
+                token,
+                input: emoji.input.clone(),
+                output_len: emoji.cps_no_fe0f.len(),
+            }),
+            EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => units.push(Unit {
+                token,
+                input: token.as_string(),
+                output_len: 0,
+            }),
+            EnsNameToken::Stop(_) => units.push(Unit {
+                token,
+                input: token.as_string(),
+                output_len: 1,
+            }),
+        }
+    }
+    units
+}
+
+/// Output positions at which a unit ends
+fn boundaries(units: &[Unit]) -> BTreeSet<usize> {
+    units
+        .iter()
+        .scan(0, |pos, unit| {
+            *pos += unit.output_len;
+            Some(*pos)
+        })
+        .collect()
+}
+
+/// Groups units into segments ending at common boundaries, keyed by the segment start.
+/// Ignored units belong to the segment that follows them.
+fn segments<'a>(
+    units: Vec<Unit<'a>>,
+    boundaries: &BTreeSet<usize>,
+) -> BTreeMap<usize, Segment<'a>> {
+    let mut segments = BTreeMap::new();
+    let mut current = Segment::default();
+    let (mut start, mut pos) = (0, 0);
+    for unit in units {
+        let output_len = unit.output_len;
+        current.units.push(unit);
+        pos += output_len;
+        if output_len > 0 && boundaries.contains(&pos) {
+            segments.insert(start, std::mem::take(&mut current));
+            start = pos;
+        }
+    }
+    if !current.units.is_empty() {
+        segments.insert(start, current);
+    }
+    segments
+}
@@ -0,0 +1,120 @@
+use crate::{normalizer::process_with, CodePointsSpecs, ProcessError, ProcessedName};
+
+/// How a name differs between two specs, see [`diff_specs`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecDiff {
+    /// Valid only under the first spec, `error` is the failure under the second one
+    OnlyA {
+        normalized: String,
+        error: ProcessError,
+    },
+    /// Valid only under the second spec, `error` is the failure under the first one
+    OnlyB {
+        normalized: String,
+        error: ProcessError,
+    },
+    /// Valid under both specs, but normalized differently
+    Normalized { a: String, b: String },
+}
+
+/// Processes the input under two specs, e.g. the current one and its upcoming version.
+/// Only ENSIP-15 rules apply, without any additional validation options.
+pub fn compare_specs(
+    input: impl AsRef<str>,
+    specs_a: &CodePointsSpecs,
+    specs_b: &CodePointsSpecs,
+) -> (
+    Result<ProcessedName, ProcessError>,
+    Result<ProcessedName, ProcessError>,
+) {
+    let input = input.as_ref();
+    let options = Default::default();
+    (
+        process_with(input, specs_a, &options),
+        process_with(input, specs_b, &options),
+    )
+}
+
+/// Returns inputs affected by switching from `specs_a` to `specs_b` together with the difference.
+/// Names valid under both specs with the same normalized form and names invalid under both are skipped.
+pub fn diff_specs<'a>(
+    inputs: impl IntoIterator<Item = &'a str>,
+    specs_a: &CodePointsSpecs,
+    specs_b: &CodePointsSpecs,
+) -> Vec<(&'a str, SpecDiff)> {
+    inputs
+        .into_iter()
+        .filter_map(|input| {
+            let diff = match compare_specs(input, specs_a, specs_b) {
+                (Ok(a), Ok(b)) => {
+                    let (a, b) = (a.normalize(), b.normalize());
+                    (a != b).then_some(SpecDiff::Normalized { a, b })
+                }
+                (Ok(a), Err(error)) => Some(SpecDiff::OnlyA {
+                    normalized: a.normalize(),
+                    error,
+                }),
+                (Err(error), Ok(b)) => Some(SpecDiff::OnlyB {
+                    normalized: b.normalize(),
+                    error,
+                }),
+                (Err(_), Err(_)) => None,
+            };
+            diff.map(|diff| (input, diff))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CodePointsSpecsBuilder;
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    #[once]
+    fn specs() -> CodePointsSpecs {
+        CodePointsSpecs::default()
+    }
+
+    /// Default spec with one extra private use emoji
+    #[fixture]
+    #[once]
+    fn extended_specs() -> CodePointsSpecs {
+        CodePointsSpecsBuilder::default()
+            .add_emoji(vec![0xE000])
+            .build()
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case::valid_in_both("Vitalik.eth", true, true)]
+    #[case::invalid_in_both("vitalik..eth", false, false)]
+    #[case::only_extended("\u{E000}.eth", false, true)]
+    fn test_compare_specs(
+        #[case] input: &str,
+        #[case] valid_a: bool,
+        #[case] valid_b: bool,
+        specs: &CodePointsSpecs,
+        extended_specs: &CodePointsSpecs,
+    ) {
+        let (a, b) = compare_specs(input, specs, extended_specs);
+        assert_eq!((a.is_ok(), b.is_ok()), (valid_a, valid_b));
+    }
+
+    #[rstest]
+    fn test_diff_specs(specs: &CodePointsSpecs, extended_specs: &CodePointsSpecs) {
+        let inputs = ["vitalik.eth", "vitalik..eth", "\u{E000}.eth"];
+        let diff = diff_specs(inputs, specs, extended_specs);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].0, "\u{E000}.eth");
+        assert!(matches!(
+            &diff[0].1,
+            SpecDiff::OnlyB { normalized, .. } if normalized == "\u{E000}.eth"
+        ));
+
+        let reversed = diff_specs(inputs, extended_specs, specs);
+        assert!(matches!(&reversed[0].1, SpecDiff::OnlyA { .. }));
+    }
+}
@@ -13,6 +13,8 @@ pub const CP_UNDERSCORE: CodePoint = 0x5F;
 pub const CP_HYPHEN: CodePoint = 0x2D;
 pub const CP_ZERO_WIDTH_JOINER: CodePoint = 0x200D;
 pub const CP_ZERO_WIDTH_NON_JOINER: CodePoint = 0x200C;
+pub const CP_SKIN_TONE_MODIFIERS: std::ops::RangeInclusive<CodePoint> = 0x1F3FB..=0x1F3FF;
+pub const CP_HALFWIDTH_FULLWIDTH_FORMS: std::ops::RangeInclusive<CodePoint> = 0xFF00..=0xFFEF;
 
 pub const GREEK_GROUP_NAME: &str = "Greek";
 pub const MAX_EMOJI_LEN: usize = 0x2d;
@@ -16,6 +16,57 @@ pub enum ProcessError {
     },
     #[error("disallowed sequence: {0}")]
     DisallowedSequence(#[from] DisallowedSequence),
+    #[error("spec version mismatch: expected {expected}, got {actual}")]
+    SpecVersionMismatch { expected: String, actual: String },
+    #[error("not in canonical form at position {index}: expected '{expected}', found '{found}'")]
+    NotCanonical {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    /// Broken invariant of the library, please report it
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl ProcessError {
+    /// Stable machine-readable identifier of the error kind, e.g. `"empty_label"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProcessError::Confused(_) => "confused",
+            ProcessError::ConfusedGroups { .. } => "confused_groups",
+            ProcessError::CurrableError { inner, .. } => inner.code(),
+            ProcessError::DisallowedSequence(inner) => inner.code(),
+            ProcessError::SpecVersionMismatch { .. } => "spec_version_mismatch",
+            ProcessError::NotCanonical { .. } => "not_canonical",
+            ProcessError::Internal(_) => "internal",
+        }
+    }
+
+    /// Position of the offending sequence inside the label, if known
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            ProcessError::CurrableError { index, .. }
+            | ProcessError::NotCanonical { index, .. }
+            | ProcessError::DisallowedSequence(DisallowedSequence::Invalid { index, .. }) => {
+                Some(*index)
+            }
+            _ => None,
+        }
+    }
+
+    /// Suggested replacement for the offending sequence, if the error is curable
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            ProcessError::CurrableError { maybe_suggest, .. } => maybe_suggest.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the error can be cured by replacing the offending sequence
+    pub fn is_curable(&self) -> bool {
+        matches!(self, ProcessError::CurrableError { .. })
+    }
 }
 
 /// Errors that can be cured by the normalizer.
@@ -29,25 +80,79 @@ pub enum CurrableError {
     CmStart,
     #[error("combining mark in disallowed position after an emoji")]
     CmAfterEmoji,
-    #[error("fenced character at the start of a label")]
-    FencedLeading,
-    #[error("fenced character at the end of a label")]
-    FencedTrailing,
-    #[error("consecutive sequence of fenced characters")]
-    FencedConsecutive,
+    #[error("fenced character ({name}) at the start of a label")]
+    FencedLeading { name: String },
+    #[error("fenced character ({name}) at the end of a label")]
+    FencedTrailing { name: String },
+    #[error("consecutive sequence of fenced characters starting with {name}")]
+    FencedConsecutive { name: String },
+    #[error("leading underscore is forbidden")]
+    LeadingUnderscoreForbidden,
+}
+
+impl CurrableError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CurrableError::UnderscoreInMiddle => "underscore_in_middle",
+            CurrableError::HyphenAtSecondAndThird => "hyphen_at_second_and_third",
+            CurrableError::CmStart => "cm_start",
+            CurrableError::CmAfterEmoji => "cm_after_emoji",
+            CurrableError::FencedLeading { .. } => "fenced_leading",
+            CurrableError::FencedTrailing { .. } => "fenced_trailing",
+            CurrableError::FencedConsecutive { .. } => "fenced_consecutive",
+            CurrableError::LeadingUnderscoreForbidden => "leading_underscore_forbidden",
+        }
+    }
 }
 
 /// Errors regarding disallowed sequences.
 #[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
 pub enum DisallowedSequence {
-    #[error("disallowed character: {0}")]
-    Invalid(String),
+    /// `index` is the code point position of `sequence` in the label input
+    #[error("disallowed character at position {index}: {sequence}")]
+    Invalid { sequence: String, index: usize },
     #[error("invisible character: {0}")]
     InvisibleCharacter(CodePoint),
+    #[error("control character: {0}")]
+    ControlCharacter(CodePoint),
     #[error("empty label")]
     EmptyLabel,
     #[error("nsm too many")]
     NsmTooMany,
     #[error("nsm repeated")]
     NsmRepeated,
+    #[error("label {label_index} is too short: {len} code points")]
+    LabelTooShort { label_index: usize, len: usize },
+    #[error("emoji is not allowed: {cps:?}")]
+    EmojiNotAllowed { cps: Vec<CodePoint> },
+    #[error("too many identical emoji in a row: {count}")]
+    TooManyConsecutiveEmoji { count: usize },
+    #[error("too many labels: {count}, at most {max} allowed")]
+    TooManyLabels { count: usize, max: usize },
+    #[error("label is not fully emoji")]
+    NotFullyEmoji,
+    #[error("unknown emoji sequence: {cps:?}")]
+    UnknownEmoji { cps: Vec<CodePoint> },
+    #[error("input is not in NFC form at code point {index}")]
+    NotNfc { index: usize },
+}
+
+impl DisallowedSequence {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DisallowedSequence::Invalid { .. } => "invalid",
+            DisallowedSequence::InvisibleCharacter(_) => "invisible_character",
+            DisallowedSequence::ControlCharacter(_) => "control_character",
+            DisallowedSequence::EmptyLabel => "empty_label",
+            DisallowedSequence::NsmTooMany => "nsm_too_many",
+            DisallowedSequence::NsmRepeated => "nsm_repeated",
+            DisallowedSequence::LabelTooShort { .. } => "label_too_short",
+            DisallowedSequence::EmojiNotAllowed { .. } => "emoji_not_allowed",
+            DisallowedSequence::TooManyConsecutiveEmoji { .. } => "too_many_consecutive_emoji",
+            DisallowedSequence::TooManyLabels { .. } => "too_many_labels",
+            DisallowedSequence::NotFullyEmoji => "not_fully_emoji",
+            DisallowedSequence::UnknownEmoji { .. } => "unknown_emoji",
+            DisallowedSequence::NotNfc { .. } => "not_nfc",
+        }
+    }
 }
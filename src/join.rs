@@ -1,26 +1,65 @@
-use crate::{constants, utils, CodePoint, EnsNameToken, ValidatedLabel};
+use crate::{constants, utils, CodePoint, EnsNameToken, TokenEmoji, ValidatedLabel};
 
 /// Joins validated labels into a string
 pub fn join_labels(labels: &[ValidatedLabel]) -> String {
+    utils::cps2str(&join_labels_cps(labels))
+}
+
+/// Joins validated labels into normalized code points
+pub fn join_labels_cps(labels: &[ValidatedLabel]) -> Vec<CodePoint> {
+    join_labels_cps_with(labels, |emoji| &emoji.cps_no_fe0f)
+}
+
+/// Joins validated labels into a string, keeping fully-qualified emoji with `FE0F`
+pub fn join_labels_qualified(labels: &[ValidatedLabel]) -> String {
+    utils::cps2str(&join_labels_cps_with(labels, |emoji| &emoji.emoji))
+}
+
+fn join_labels_cps_with(
+    labels: &[ValidatedLabel],
+    emoji_cps: impl Fn(&TokenEmoji) -> &Vec<CodePoint>,
+) -> Vec<CodePoint> {
     let labels_cps = labels.iter().map(|label| {
         label
             .tokens
             .iter()
-            .filter_map(|token| match token {
-                EnsNameToken::Disallowed(_) | EnsNameToken::Ignored(_) | EnsNameToken::Stop(_) => {
-                    None
-                }
-                EnsNameToken::Valid(token) => Some(&token.cps),
-                EnsNameToken::Mapped(token) => Some(&token.cps),
-                EnsNameToken::Nfc(token) => Some(&token.cps),
-                EnsNameToken::Emoji(token) => Some(&token.cps_no_fe0f),
-            })
+            .filter_map(|token| token_output_cps(token, &emoji_cps))
             .flatten()
             .cloned()
             .collect::<Vec<_>>()
     });
 
-    join_cps(labels_cps)
+    itertools::intersperse(labels_cps, vec![constants::CP_STOP])
+        .flatten()
+        .collect()
+}
+
+/// Joins raw tokens of a whole name into a string, stop tokens included, without validating them
+pub fn join_tokens(tokens: &[EnsNameToken]) -> String {
+    let cps = tokens
+        .iter()
+        .filter_map(|token| match token {
+            EnsNameToken::Stop(token) => Some(std::slice::from_ref(&token.cp)),
+            _ => token_output_cps(token, &|emoji| &emoji.cps_no_fe0f).map(Vec::as_slice),
+        })
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+    utils::cps2str(&cps)
+}
+
+/// Code points a token contributes to a label, `None` for tokens without output
+fn token_output_cps<'a>(
+    token: &'a EnsNameToken,
+    emoji_cps: &impl Fn(&TokenEmoji) -> &Vec<CodePoint>,
+) -> Option<&'a Vec<CodePoint>> {
+    match token {
+        EnsNameToken::Disallowed(_) | EnsNameToken::Ignored(_) | EnsNameToken::Stop(_) => None,
+        EnsNameToken::Valid(token) => Some(&token.cps),
+        EnsNameToken::Mapped(token) => Some(&token.cps),
+        EnsNameToken::Nfc(token) => Some(&token.cps),
+        EnsNameToken::Emoji(token) => Some(emoji_cps(token)),
+    }
 }
 
 /// Joins code points into a string
@@ -1,16 +1,42 @@
 mod beautify;
 mod code_points;
+mod collision;
+mod compare;
 pub(crate) mod constants;
 mod error;
 mod join;
+#[cfg(feature = "namehash")]
+mod namehash;
 mod normalizer;
+mod report;
 mod static_data;
 mod tokens;
 mod utils;
 mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wire;
 
-pub(crate) use code_points::*;
+pub use beautify::beautify_labels;
+pub use code_points::*;
+pub use collision::CollisionReason;
+pub use compare::{compare_specs, diff_specs, SpecDiff};
 pub use error::{CurrableError, DisallowedSequence, ProcessError};
-pub use normalizer::{beautify, normalize, process, tokenize, EnsNameNormalizer, ProcessedName};
+pub use join::join_labels;
+#[cfg(feature = "namehash")]
+pub use namehash::namehash_from_labels;
+pub use normalizer::{
+    beautify, collision_explanation, ens_emoji, names_emoji_equivalent, normalize, process,
+    tokenize, DiffOp, DisallowedPolicy, EnsNameNormalizer, LabelReport, LabelResult, ProcessedName,
+};
+#[cfg(feature = "namehash")]
+pub use normalizer::{labelhash, namehash, namehash_dns};
+pub use report::{NormalizationReport, Transform, TransformKind, Warning};
+pub use static_data::spec_json::Script;
 pub use tokens::*;
-pub use validate::{LabelType, ValidatedLabel};
+pub use utils::safe_display;
+pub use validate::{
+    confusability_notes, validate_label, validate_name, validate_name_all, validate_name_partial,
+    validate_name_ref, ConfusabilityNote, EmojiPolicy, LabelType, LabelValidator, ValidatedLabel,
+    ValidatedLabelRef, ValidationOptions,
+};
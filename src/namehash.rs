@@ -0,0 +1,63 @@
+use crate::{join_labels, ValidatedLabel};
+use tiny_keccak::{Hasher, Keccak};
+
+pub(crate) fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(bytes);
+    hasher.finalize(&mut output);
+    output
+}
+
+/// EIP-137 namehash of normalized labels, given from left to right as in the name
+pub(crate) fn namehash_labels<I, S>(labels: I) -> [u8; 32]
+where
+    I: IntoIterator<Item = S>,
+    I::IntoIter: DoubleEndedIterator,
+    S: AsRef<str>,
+{
+    labels.into_iter().rev().fold([0u8; 32], |node, label| {
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(&node);
+        data[32..].copy_from_slice(&keccak256(label.as_ref().as_bytes()));
+        keccak256(&data)
+    })
+}
+
+/// EIP-137 namehash of already validated labels, e.g. [`crate::ProcessedName::labels`].
+/// The root (no labels) hashes to 32 zero bytes.
+pub fn namehash_from_labels(labels: &[ValidatedLabel]) -> [u8; 32] {
+    namehash_labels(
+        labels
+            .iter()
+            .map(|label| join_labels(std::slice::from_ref(label))),
+    )
+}
+
+/// keccak256 of each validated label, in order
+pub(crate) fn labelhashes(labels: &[ValidatedLabel]) -> Vec<[u8; 32]> {
+    labels
+        .iter()
+        .map(|label| keccak256(join_labels(std::slice::from_ref(label)).as_bytes()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::root(&[], "0000000000000000000000000000000000000000000000000000000000000000")]
+    #[case::eth(&["eth"], "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae")]
+    #[case::foo_eth(
+        &["foo", "eth"],
+        "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+    )]
+    fn test_namehash_labels(#[case] labels: &[&str], #[case] expected: &str) {
+        let hash = namehash_labels(labels.iter());
+        let hex = hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        assert_eq!(hex, expected);
+    }
+}
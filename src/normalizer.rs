@@ -1,41 +1,400 @@
 use crate::{
-    beautify::beautify_labels, join::join_labels, validate::validate_name, CodePointsSpecs,
-    ProcessError, TokenizedName, ValidatedLabel,
+    beautify::beautify_labels,
+    collision::{explain_collision, CollisionReason},
+    constants,
+    join::{join_labels, join_labels_cps, join_labels_qualified},
+    report::{build_report, NormalizationReport},
+    utils,
+    validate::{
+        confusability_notes, validate_name, validate_name_all, validate_name_partial,
+        validate_name_ref, EmojiPolicy, ValidationOptions,
+    },
+    wire, CodePoint, CodePointsSpecs, ConfusabilityNote, DisallowedSequence, EnsNameToken,
+    LabelType, ProcessError, TokenIgnored, TokenMapped, TokenizedName, ValidatedLabel,
+    ValidatedLabelRef,
 };
+use lazy_static::lazy_static;
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+    sync::Arc,
+};
+
+lazy_static! {
+    /// Bundled specs shared by functions that only read them, e.g. [`ens_emoji`]
+    static ref BUNDLED_SPECS: CodePointsSpecs = CodePointsSpecs::default();
+}
 
 /// Main struct to handle ENS name normalization including
-/// tokenization, validation, beautification and normalization
-#[derive(Default)]
+/// tokenization, validation, beautification and normalization.
+///
+/// Specs are shared between clones, so cloning a normalizer is cheap.
+#[derive(Default, Clone)]
 pub struct EnsNameNormalizer {
-    specs: CodePointsSpecs,
+    specs: Arc<CodePointsSpecs>,
+    options: ValidationOptions,
+    disallowed_policy: DisallowedPolicy,
+    required_spec_version: Option<String>,
+    premap: HashMap<CodePoint, Vec<CodePoint>>,
 }
 
 /// Result of processing an ENS name.
 /// Contains tokenized name as intermediate processing result and validated labels.
 /// Validated labels can be normalized and beautified.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Two processed names are equal if they normalize to the same name, e.g. `A.eth` and `a.eth`,
+/// regardless of the original input. Compare [`ProcessedName::tokenized`] to tell the inputs apart.
+#[derive(Debug, Clone)]
 pub struct ProcessedName {
     pub labels: Vec<ValidatedLabel>,
     pub tokenized: TokenizedName,
+    pub(crate) substitutions: usize,
+}
+
+impl PartialEq for ProcessedName {
+    fn eq(&self, other: &Self) -> bool {
+        join_labels_cps(&self.labels) == join_labels_cps(&other.labels)
+    }
+}
+
+impl Eq for ProcessedName {}
+
+/// What to do with disallowed characters, see [`EnsNameNormalizer::on_disallowed`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisallowedPolicy {
+    /// Fail with [`ProcessError::DisallowedSequence`], as required by ENSIP-15
+    #[default]
+    Error,
+    /// Drop disallowed characters
+    Remove,
+    /// Replace every disallowed character with the given one, e.g. `U+FFFD`
+    Replace(char),
 }
 
 impl EnsNameNormalizer {
     pub fn new(specs: CodePointsSpecs) -> Self {
-        Self { specs }
+        Self {
+            specs: Arc::new(specs),
+            options: ValidationOptions::default(),
+            disallowed_policy: DisallowedPolicy::default(),
+            required_spec_version: None,
+            premap: HashMap::new(),
+        }
+    }
+
+    /// Returns the specs used by the normalizer
+    pub fn specs(&self) -> &CodePointsSpecs {
+        &self.specs
+    }
+
+    /// Unicode version of the loaded spec, see [`CodePointsSpecs::unicode_version`]
+    pub fn unicode_version(&self) -> &str {
+        self.specs.unicode_version()
+    }
+
+    /// CLDR version of the loaded spec, see [`CodePointsSpecs::cldr_version`]
+    pub fn cldr_version(&self) -> &str {
+        self.specs.cldr_version()
+    }
+
+    /// Creation timestamp of the loaded spec, see [`CodePointsSpecs::created`]
+    pub fn created(&self) -> &str {
+        self.specs.created()
+    }
+
+    /// Fail processing with [`ProcessError::SpecVersionMismatch`] unless the loaded specs
+    /// have the given [`CodePointsSpecs::fingerprint`]. Useful when several parties must agree on the spec.
+    pub fn require_spec_version(&mut self, fingerprint: &str) {
+        self.required_spec_version = Some(fingerprint.to_string());
+    }
+
+    /// Reject labels with less than `len` code points in their normalized form.
+    /// Code points are counted, not graphemes, so an emoji sequence counts as several characters.
+    /// Not part of ENSIP-15, disabled by default (`0`).
+    pub fn min_label_length(mut self, len: usize) -> Self {
+        self.options.min_label_length = len;
+        self
+    }
+
+    /// Reject labels starting with an underscore.
+    /// Not part of ENSIP-15, disabled by default.
+    pub fn forbid_leading_underscore(mut self, forbid: bool) -> Self {
+        self.options.forbid_leading_underscore = forbid;
+        self
+    }
+
+    /// Restrict which emoji are accepted, see [`EmojiPolicy`].
+    /// Not part of ENSIP-15, all emoji are allowed by default.
+    pub fn emoji_policy(mut self, policy: EmojiPolicy) -> Self {
+        self.options.emoji_policy = policy;
+        self
+    }
+
+    /// Reject labels with more than `max` identical emoji in a row, e.g. `😀😀😀`.
+    /// Not part of ENSIP-15, no limit by default.
+    pub fn max_consecutive_emoji(mut self, max: usize) -> Self {
+        self.options.max_consecutive_emoji = Some(max);
+        self
+    }
+
+    /// Reject names with more than `max` labels, e.g. to limit subdomain nesting depth.
+    /// Not part of ENSIP-15, no limit by default.
+    pub fn max_labels(mut self, max: usize) -> Self {
+        self.options.max_labels = Some(max);
+        self
+    }
+
+    /// Reject names whose input was not already in NFC form, e.g. `e` followed by `U+0301`
+    /// instead of precomposed `é`, with [`DisallowedSequence::NotNfc`].
+    /// Not part of ENSIP-15, disabled by default.
+    pub fn require_nfc(mut self, require: bool) -> Self {
+        self.options.require_nfc = require;
+        self
+    }
+
+    /// Remove or replace disallowed characters instead of failing, see [`DisallowedPolicy`].
+    /// The rest of the name is validated as usual and control characters are always rejected.
+    /// Meant for lenient display only: a name with substitutions is not the input's normalized form.
+    pub fn on_disallowed(mut self, policy: DisallowedPolicy) -> Self {
+        self.disallowed_policy = policy;
+        self
+    }
+
+    /// Replace code points of the input before tokenization, e.g. to read a private use
+    /// code point as a specific letter. The rest of the pipeline only sees the replacements,
+    /// and [`TokenizedName::input`] holds the premapped input.
+    /// Not part of ENSIP-15: names relying on it won't resolve on mainnet.
+    ///
+    /// Positions are not mapped back to the caller's input: byte ranges and slices of
+    /// [`ProcessedName::input_label`], [`ProcessedName::source_map`] and
+    /// [`ProcessedName::input_vs_normalized_diff`], as well as [`LabelResult::input`] and
+    /// [`LabelResult::offset`] of [`EnsNameNormalizer::split`], refer to the premapped input.
+    /// They differ from the original whenever a replacement changes the length.
+    pub fn with_premap(mut self, map: HashMap<CodePoint, Vec<CodePoint>>) -> Self {
+        self.premap = map;
+        self
     }
 
     /// Tokenize the input string, return a `TokenizedName` object with `Vec<EnsNameToken>` inside
     pub fn tokenize(&self, input: impl AsRef<str>) -> Result<TokenizedName, ProcessError> {
-        TokenizedName::from_input(input.as_ref(), &self.specs, true)
+        self.tokenize_premapped(&self.premapped(input.as_ref()))
+    }
+
+    /// Same as [`EnsNameNormalizer::tokenize`] for input already passed through [`EnsNameNormalizer::premapped`]
+    fn tokenize_premapped(&self, input: &str) -> Result<TokenizedName, ProcessError> {
+        TokenizedName::from_input(input, &self.specs, true)
+    }
+
+    /// Applies [`EnsNameNormalizer::with_premap`] replacements, borrowing the input if there are none
+    fn premapped<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        if !input
+            .chars()
+            .any(|c| self.premap.contains_key(&(c as CodePoint)))
+        {
+            return Cow::Borrowed(input);
+        }
+        Cow::Owned(
+            input
+                .chars()
+                .map(|c| match self.premap.get(&(c as CodePoint)) {
+                    Some(cps) => utils::cps2str(cps),
+                    None => c.to_string(),
+                })
+                .collect(),
+        )
     }
 
     /// Process the input string, return a `ProcessedName` object with `Vec<ValidatedLabel>` inside
     /// This function will tokenize and validate the name. Processed name can be normalized and beautified.
     pub fn process(&self, input: impl AsRef<str>) -> Result<ProcessedName, ProcessError> {
-        let input = input.as_ref();
-        let tokenized = self.tokenize(input)?;
-        let labels = validate_name(&tokenized, &self.specs)?;
-        Ok(ProcessedName { tokenized, labels })
+        self.process_premapped(&self.premapped(input.as_ref()))
+    }
+
+    /// Same as [`EnsNameNormalizer::process`] for input already passed through [`EnsNameNormalizer::premapped`]
+    fn process_premapped(&self, input: &str) -> Result<ProcessedName, ProcessError> {
+        self.check_spec_version()?;
+        no_control_characters(input)?;
+        self.process_tokenized(self.tokenize_premapped(input)?)
+    }
+
+    /// Validates a name tokenized from premapped input, applying the disallowed policy
+    fn process_tokenized(&self, tokenized: TokenizedName) -> Result<ProcessedName, ProcessError> {
+        if self.disallowed_policy == DisallowedPolicy::Error {
+            let labels = validate_name(&tokenized, &self.specs, &self.options)?;
+            return Ok(ProcessedName {
+                tokenized,
+                labels,
+                substitutions: 0,
+            });
+        }
+        let (without_disallowed, tokenized, substitutions) = self.substitute_disallowed(tokenized);
+        let validated = validate_name(&without_disallowed, &self.specs, &self.options)?;
+        let labels = tokenized
+            .iter_labels()
+            .zip(validated)
+            .map(|(label, validated)| ValidatedLabel {
+                tokens: label.tokens.to_vec(),
+                ..validated
+            })
+            .collect();
+        Ok(ProcessedName {
+            tokenized,
+            labels,
+            substitutions,
+        })
+    }
+
+    /// Validates disallowed characters as ignored, see [`DisallowedPolicy`].
+    /// Returns the name to validate, the name to output and the number of substitutions.
+    /// With [`DisallowedPolicy::Error`] both names are the input, so validation fails on them.
+    fn substitute_disallowed(
+        &self,
+        mut tokenized: TokenizedName,
+    ) -> (TokenizedName, TokenizedName, usize) {
+        if self.disallowed_policy == DisallowedPolicy::Error {
+            return (tokenized.clone(), tokenized, 0);
+        }
+        let mut without_disallowed = tokenized.clone();
+        let substitutions = replace_disallowed(&mut without_disallowed, |cp| {
+            EnsNameToken::Ignored(TokenIgnored { cp })
+        });
+        match self.disallowed_policy {
+            DisallowedPolicy::Replace(replacement) => {
+                replace_disallowed(&mut tokenized, |cp| {
+                    EnsNameToken::Mapped(TokenMapped {
+                        cp,
+                        cps: vec![replacement as CodePoint],
+                    })
+                });
+            }
+            _ => tokenized = without_disallowed.clone(),
+        }
+        (without_disallowed, tokenized, substitutions)
+    }
+
+    /// Splits the input into labels and validates each of them independently,
+    /// so one bad label does not hide the others, e.g. only the middle label of
+    /// `good.BAD!.good` fails. Empty input has no labels.
+    ///
+    /// Name-wide options, i.e. `max_labels` and `require_nfc`, are not checked.
+    /// If the input cannot be tokenized at all, the only result covers the whole input.
+    pub fn split(&self, input: impl AsRef<str>) -> Vec<LabelResult> {
+        let input = self.premapped(input.as_ref());
+        let tokenized = match self
+            .check_spec_version()
+            .and_then(|_| no_control_characters(&input))
+            .and_then(|_| self.tokenize_premapped(&input))
+        {
+            Ok(tokenized) => tokenized,
+            Err(err) => {
+                return vec![LabelResult {
+                    input: input.to_string(),
+                    offset: 0,
+                    tokens: vec![],
+                    result: Err(err),
+                }]
+            }
+        };
+        if tokenized.is_empty() {
+            return vec![];
+        }
+        let (without_disallowed, output, _) = self.substitute_disallowed(tokenized.clone());
+        let mut offset = 0;
+        let mut results = vec![];
+        for (label_index, ((label, validated), output)) in tokenized
+            .iter_labels()
+            .zip(validate_name_all(
+                &without_disallowed,
+                &self.specs,
+                &self.options,
+            ))
+            .zip(output.iter_labels())
+            .enumerate()
+        {
+            results.push(LabelResult {
+                input: tokenized
+                    .input_label(label_index)
+                    .unwrap_or_default()
+                    .to_string(),
+                offset,
+                tokens: label.tokens.to_vec(),
+                result: validated.1.map(|validated| ValidatedLabel {
+                    tokens: output.tokens.to_vec(),
+                    ..validated
+                }),
+            });
+            // one more code point for the stop
+            offset += label
+                .tokens
+                .iter()
+                .map(EnsNameToken::input_size)
+                .sum::<usize>()
+                + 1;
+        }
+        results
+    }
+
+    /// Same as [`EnsNameNormalizer::process`], but on failure keeps the labels validated
+    /// before the failing one, e.g. to render the valid part of a name and flag the bad label.
+    /// Validation stops at the first error.
+    pub fn process_partial(
+        &self,
+        input: impl AsRef<str>,
+    ) -> (Vec<ValidatedLabel>, Option<ProcessError>) {
+        let input = self.premapped(input.as_ref());
+        let tokenized = match self
+            .check_spec_version()
+            .and_then(|_| no_control_characters(&input))
+            .and_then(|_| self.tokenize_premapped(&input))
+        {
+            Ok(tokenized) => tokenized,
+            Err(err) => return (vec![], Some(err)),
+        };
+        let (without_disallowed, output, _) = self.substitute_disallowed(tokenized);
+        let (labels, err) = validate_name_partial(&without_disallowed, &self.specs, &self.options);
+        let labels = labels
+            .into_iter()
+            .zip(output.iter_labels())
+            .map(|(validated, output)| ValidatedLabel {
+                tokens: output.tokens.to_vec(),
+                ..validated.into_owned()
+            })
+            .collect();
+        (labels, err)
+    }
+
+    /// Same as [`EnsNameNormalizer::process_partial`], but validation goes on after a failing label:
+    /// returns the valid labels together with the errors of the others by label index,
+    /// see [`EnsNameNormalizer::split`]. Name-wide options, i.e. `max_labels` and `require_nfc`, are not checked,
+    /// errors of the whole input, e.g. control characters, are reported at index `0`.
+    pub fn process_lenient(
+        &self,
+        input: impl AsRef<str>,
+    ) -> (Vec<ValidatedLabel>, Vec<(usize, ProcessError)>) {
+        let mut labels = vec![];
+        let mut errors = vec![];
+        for (label_index, label) in self.split(input).into_iter().enumerate() {
+            match label.result {
+                Ok(label) => labels.push(label),
+                Err(err) => errors.push((label_index, err)),
+            }
+        }
+        (labels, errors)
+    }
+
+    /// Validate an already tokenized name without cloning its tokens:
+    /// the validated labels borrow from `tokenized`.
+    /// Useful for read-only workflows, e.g. computing a hash or displaying labels.
+    /// Borrowed tokens cannot be substituted, so disallowed characters always fail
+    /// as with [`DisallowedPolicy::Error`], whatever [`EnsNameNormalizer::on_disallowed`] says.
+    pub fn process_ref<'a>(
+        &self,
+        tokenized: &'a TokenizedName,
+    ) -> Result<Vec<ValidatedLabelRef<'a>>, ProcessError> {
+        self.check_spec_version()?;
+        no_control_characters(&tokenized.input)?;
+        validate_name_ref(tokenized, &self.specs, &self.options)
     }
 
     /// Normalize the input string, return a normalized version of ENS name
@@ -43,13 +402,374 @@ impl EnsNameNormalizer {
         self.process(input).map(|processed| processed.normalize())
     }
 
+    /// Succeeds only if the input is already normalized, i.e. no code point is mapped, ignored,
+    /// composed or stripped of `FE0F`. Otherwise fails with [`ProcessError::NotCanonical`] at the first
+    /// deviation, `index` being the code point position in the input.
+    pub fn validate_canonical(&self, input: impl AsRef<str>) -> Result<(), ProcessError> {
+        let processed = self.process(input)?;
+        let mut index = 0;
+        for token in &processed.tokenized.tokens {
+            let found = match token {
+                EnsNameToken::Valid(_) | EnsNameToken::Stop(_) => None,
+                EnsNameToken::Emoji(emoji) if emoji.cps_input == emoji.cps_no_fe0f => None,
+                EnsNameToken::Emoji(emoji) => Some(emoji.input.clone()),
+                EnsNameToken::Mapped(mapped) => Some(utils::cp2str(mapped.cp)),
+                EnsNameToken::Nfc(nfc) => Some(utils::cps2str(&nfc.input)),
+                EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => Some(token.as_string()),
+            };
+            if let Some(found) = found {
+                let expected = match token {
+                    EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => String::new(),
+                    _ => token.as_string(),
+                };
+                return Err(ProcessError::NotCanonical {
+                    index,
+                    expected,
+                    found,
+                });
+            }
+            index += token.input_size();
+        }
+        Ok(())
+    }
+
+    /// Returns true if the normalized input is in the blocklist of normalized names.
+    /// To catch confusable variants too, build the blocklist with
+    /// [`EnsNameNormalizer::normalize_skeleton_for_blocklist`] and compare the inputs with it instead.
+    pub fn is_blocked(
+        &self,
+        input: impl AsRef<str>,
+        blocklist: &HashSet<String>,
+    ) -> Result<bool, ProcessError> {
+        Ok(blocklist.contains(&self.normalize(input)?))
+    }
+
+    /// Lossy key matching confusable variants of a name, see [`ProcessedName::search_key`].
+    /// Apply it to both blocklist entries and inputs.
+    pub fn normalize_skeleton_for_blocklist(
+        &self,
+        input: impl AsRef<str>,
+    ) -> Result<String, ProcessError> {
+        self.process(input)
+            .map(|processed| processed.search_key(&self.specs))
+    }
+
+    /// Normalize partial input, e.g. `raffy.e` in a search-as-you-type box: code points are mapped,
+    /// ignored, composed and emoji stripped of `FE0F`, but labels are not validated,
+    /// so empty labels, leading combining marks or mixed scripts are accepted.
+    /// Disallowed characters are still rejected, unless [`EnsNameNormalizer::on_disallowed`] says otherwise,
+    /// with the code point index in their label as validation reports it.
+    pub fn normalize_fragment(&self, input: impl AsRef<str>) -> Result<String, ProcessError> {
+        self.check_spec_version()?;
+        let input = self.premapped(input.as_ref());
+        no_control_characters(&input)?;
+        let tokenized = self.tokenize_premapped(&input)?;
+        if self.disallowed_policy == DisallowedPolicy::Error {
+            // relative to the label, as in validation
+            let mut index = 0;
+            for token in &tokenized.tokens {
+                if token.is_stop() {
+                    index = 0;
+                    continue;
+                }
+                if token.is_disallowed() {
+                    return Err(ProcessError::DisallowedSequence(
+                        DisallowedSequence::Invalid {
+                            sequence: token.as_string(),
+                            index,
+                        },
+                    ));
+                }
+                index += token.input_size();
+            }
+            return Ok(tokenized.normalized_unchecked());
+        }
+        let (_, output, _) = self.substitute_disallowed(tokenized);
+        Ok(output.normalized_unchecked())
+    }
+
+    /// Normalize a single label made of emoji only, e.g. for emoji-domain marketplaces.
+    /// Fails with [`DisallowedSequence::NotFullyEmoji`] for any other label, including names with several labels.
+    pub fn normalize_emoji_label(&self, input: impl AsRef<str>) -> Result<String, ProcessError> {
+        let processed = self.process(input)?;
+        match processed.labels.as_slice() {
+            [label] if label.label_type == LabelType::Emoji => Ok(processed.normalize()),
+            _ => Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::NotFullyEmoji,
+            )),
+        }
+    }
+
+    /// Normalize a name, fixing curable errors of every label as [`EnsNameNormalizer::cure_label`] does,
+    /// e.g. `xn--vitalik.e_th` is cured to `xnvitalik.eth`. Errors that cannot be cured are returned as is.
+    pub fn cure(&self, input: impl AsRef<str>) -> Result<String, ProcessError> {
+        let input = self.premapped(input.as_ref());
+        if input.is_empty() {
+            return self
+                .process_premapped(&input)
+                .map(|processed| processed.normalize());
+        }
+        let stop = char::from_u32(constants::CP_STOP).expect("stop is a valid char");
+        let cured = input
+            .split(stop)
+            .map(|label| self.cure_premapped_label(label.to_string()))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(&stop.to_string());
+        // name-wide checks, e.g. the number of labels
+        self.process_premapped(&cured)
+            .map(|processed| processed.normalize())
+    }
+
+    /// Normalize a single label, fixing curable errors instead of failing on them:
+    /// underscores in the middle and hyphens at the 3rd and 4th positions are removed,
+    /// leading and trailing fenced characters are trimmed, consecutive ones are merged,
+    /// and leading combining marks are dropped. Fails on any error that cannot be cured,
+    /// with [`DisallowedSequence::Invalid`] if the input has several labels.
+    /// Gives up with the last curable error after [`max_cure_steps`] attempts.
+    pub fn cure_label(&self, label: impl AsRef<str>) -> Result<String, ProcessError> {
+        self.cure_premapped_label(self.premapped(label.as_ref()).into_owned())
+    }
+
+    /// Same as [`EnsNameNormalizer::cure_label`] for a label already passed through
+    /// [`EnsNameNormalizer::premapped`]: cured labels are normalized, so they must not be premapped again
+    fn cure_premapped_label(&self, mut current: String) -> Result<String, ProcessError> {
+        let max_steps = max_cure_steps(&self.tokenize_premapped(&current)?.normalized_unchecked());
+        let mut last_err = None;
+        for _ in 0..max_steps {
+            let tokenized = self.tokenize_premapped(&current)?;
+            if tokenized.is_empty() {
+                return Err(ProcessError::DisallowedSequence(
+                    DisallowedSequence::EmptyLabel,
+                ));
+            }
+            single_label(&tokenized)?;
+            let err = match self.process_premapped(&current) {
+                Ok(processed) => return Ok(processed.normalize()),
+                Err(err @ ProcessError::CurrableError { .. }) => err,
+                Err(err) => return Err(err),
+            };
+            // error positions match code points only once the label is normalized
+            let normalized = tokenized.normalized_unchecked();
+            if normalized != current {
+                current = normalized;
+            } else {
+                current = apply_cure(&current, &err).ok_or_else(|| err.clone())?;
+            }
+            last_err = Some(err);
+        }
+        Err(last_err.expect("at least one cure step"))
+    }
+
+    /// EIP-137 namehash of a DNS-style name: a single trailing dot denoting the root
+    /// is dropped before normalization, so `vitalik.eth.` hashes as `vitalik.eth`
+    #[cfg(feature = "namehash")]
+    pub fn namehash_dns(&self, input: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+        let input = input.as_ref();
+        let processed = self.process(input.strip_suffix('.').unwrap_or(input))?;
+        Ok(processed.namehash())
+    }
+
+    /// keccak256 of a single normalized label, e.g. for `setSubnodeRecord` calls.
+    /// Inputs containing a label separator are rejected.
+    #[cfg(feature = "namehash")]
+    pub fn labelhash(&self, label: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+        self.check_spec_version()?;
+        let label = self.premapped(label.as_ref());
+        no_control_characters(&label)?;
+        let tokenized = self.tokenize_premapped(&label)?;
+        if tokenized.is_empty() {
+            return Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::EmptyLabel,
+            ));
+        }
+        single_label(&tokenized)?;
+        let processed = self.process_tokenized(tokenized)?;
+        Ok(crate::namehash::keccak256(processed.normalize().as_bytes()))
+    }
+
+    /// EIP-137 namehash of the normalized name, e.g. for ENS registry calls.
+    /// The empty name is the root and hashes to 32 zero bytes.
+    #[cfg(feature = "namehash")]
+    pub fn namehash(&self, input: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+        self.process(input).map(|processed| processed.namehash())
+    }
+
+    /// Normalize the input string and compute its EIP-137 namehash from the same validated labels
+    #[cfg(feature = "namehash")]
+    pub fn normalize_and_namehash(
+        &self,
+        input: impl AsRef<str>,
+    ) -> Result<(String, [u8; 32]), ProcessError> {
+        let processed = self.process(input)?;
+        Ok((processed.normalize(), processed.namehash()))
+    }
+
+    /// Normalize the input string and return the type of its second-level label,
+    /// e.g. `vitalik` in `vitalik.eth`. The type is `None` for names with less than two labels.
+    pub fn normalize_with_group(
+        &self,
+        input: impl AsRef<str>,
+    ) -> Result<(String, Option<LabelType>), ProcessError> {
+        let processed = self.process(input)?;
+        let group = processed
+            .labels
+            .len()
+            .checked_sub(2)
+            .and_then(|index| processed.label(index))
+            .map(|label| label.label_type.clone());
+        Ok((processed.normalize(), group))
+    }
+
+    /// Normalize all inputs, succeeding only if every input is valid.
+    /// Otherwise returns all failures together with the index of the failed input.
+    pub fn normalize_batch_atomic<I>(
+        &self,
+        inputs: impl IntoIterator<Item = I>,
+    ) -> Result<Vec<String>, Vec<(usize, ProcessError)>>
+    where
+        I: AsRef<str>,
+    {
+        let mut normalized = Vec::new();
+        let mut errors = Vec::new();
+        for (index, input) in inputs.into_iter().enumerate() {
+            match self.normalize(input) {
+                Ok(name) => normalized.push(name),
+                Err(err) => errors.push((index, err)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(normalized)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Normalize the input string, borrowing the input if it is already normalized.
+    /// Avoids allocating a new string in the common case of already normalized names.
+    pub fn normalize_cow<'a>(&self, input: &'a str) -> Result<Cow<'a, str>, ProcessError> {
+        let processed = self.process(input)?;
+        if processed.is_input_normalized(input) {
+            Ok(Cow::Borrowed(input))
+        } else {
+            Ok(Cow::Owned(processed.normalize()))
+        }
+    }
+
+    /// Normalize the input and report everything about it in one pass: beautified form,
+    /// label types, transforms applied to the input and warnings, see [`NormalizationReport`]
+    pub fn normalize_with_report(
+        &self,
+        input: impl AsRef<str>,
+    ) -> Result<NormalizationReport, ProcessError> {
+        let processed = self.process(input)?;
+        Ok(build_report(&processed, &self.specs))
+    }
+
+    /// Normalize the input string, also telling whether normalization changed it.
+    /// The flag is `true` if the normalized name differs from the input byte-for-byte.
+    pub fn normalize_checked(
+        &self,
+        input: impl AsRef<str>,
+    ) -> Result<(String, bool), ProcessError> {
+        let input = input.as_ref();
+        let processed = self.process(input)?;
+        Ok((processed.normalize(), !processed.is_input_normalized(input)))
+    }
+
+    /// Returns true if the input string is a valid and already normalized ENS name
+    pub fn is_normalized(&self, input: impl AsRef<str>) -> bool {
+        let input = input.as_ref();
+        self.process(input)
+            .map(|processed| processed.is_input_normalized(input))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if two names are equal up to emoji presentation, i.e. ignoring `FE0F`.
+    /// Unlike full normalization, the rest of the names is compared as is.
+    pub fn names_emoji_equivalent(
+        &self,
+        a: impl AsRef<str>,
+        b: impl AsRef<str>,
+    ) -> Result<bool, ProcessError> {
+        let a = TokenizedName::from_input(a.as_ref(), &self.specs, false)?;
+        let b = TokenizedName::from_input(b.as_ref(), &self.specs, false)?;
+        Ok(emoji_insensitive_cps(&a) == emoji_insensitive_cps(&b))
+    }
+
+    /// Explains why two different inputs normalize to the same name, listing the transforms
+    /// that differ between them together with their code point index in the normalized name.
+    /// Returns an empty list if the inputs do not normalize to the same name.
+    pub fn collision_explanation(
+        &self,
+        a: impl AsRef<str>,
+        b: impl AsRef<str>,
+    ) -> Result<Vec<(usize, CollisionReason)>, ProcessError> {
+        let a = self.process(a)?;
+        let b = self.process(b)?;
+        if a.normalize() != b.normalize() {
+            return Ok(vec![]);
+        }
+        Ok(explain_collision(&a, &b))
+    }
+
     /// Beautify the input string, return a beautified version of ENS name/// Beautify the input string, return a beautified version of ENS name
     pub fn beautify(&self, input: impl AsRef<str>) -> Result<String, ProcessError> {
         self.process(input).map(|processed| processed.beautify())
     }
+
+    /// Beautify already validated labels without running validation again,
+    /// e.g. labels stored from a previous [`EnsNameNormalizer::process`] call
+    pub fn beautify_cps_only(&self, labels: &[ValidatedLabel]) -> String {
+        beautify_labels(labels)
+    }
+
+    fn check_spec_version(&self) -> Result<(), ProcessError> {
+        match &self.required_spec_version {
+            Some(expected) if expected != self.specs.fingerprint() => {
+                Err(ProcessError::SpecVersionMismatch {
+                    expected: expected.clone(),
+                    actual: self.specs.fingerprint().to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Character-level edit turning the original input into the normalized name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOp {
+    Keep(String),
+    Replace { from: String, to: String },
+    Remove(String),
+}
+
+/// One label of [`EnsNameNormalizer::split`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelResult {
+    /// Slice of the input covered by the label
+    pub input: String,
+    /// Code point position of the label in the input
+    pub offset: usize,
+    pub tokens: Vec<EnsNameToken>,
+    pub result: Result<ValidatedLabel, ProcessError>,
+}
+
+/// Per-label summary of a processed name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelReport {
+    pub normalized: String,
+    pub label_type: LabelType,
+    pub restricted: bool,
 }
 
 impl ProcessedName {
+    /// Number of disallowed characters removed or replaced, see [`DisallowedPolicy`]
+    pub fn substitutions(&self) -> usize {
+        self.substitutions
+    }
+
+    /// Normalized name, emoji are always written without `FE0F`
     pub fn normalize(&self) -> String {
         join_labels(&self.labels)
     }
@@ -57,6 +777,324 @@ impl ProcessedName {
     pub fn beautify(&self) -> String {
         beautify_labels(&self.labels)
     }
+
+    /// Same as [`ProcessedName::normalize`], but emoji are fully-qualified, i.e. keep `FE0F`.
+    /// Unlike [`ProcessedName::beautify`], the rest of the name is left normalized.
+    pub fn normalize_qualified(&self) -> String {
+        join_labels_qualified(&self.labels)
+    }
+
+    /// Compact binary encoding of the labels, enough to restore [`ProcessedName::normalize`]
+    /// and [`ProcessedName::beautify`] without processing the name again, e.g. for caching.
+    /// Ignored and disallowed input characters are not kept.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        wire::encode(self)
+    }
+
+    /// Decodes a name encoded with [`ProcessedName::to_bytes`].
+    /// The tokenized name is rebuilt from the labels, so its `input` is the normalized name.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        wire::decode(bytes)
+    }
+
+    /// EIP-137 namehash of the normalized name, see [`crate::namehash_from_labels`]
+    #[cfg(feature = "namehash")]
+    pub fn namehash(&self) -> [u8; 32] {
+        crate::namehash::namehash_from_labels(&self.labels)
+    }
+
+    /// keccak256 of each normalized label, in order
+    #[cfg(feature = "namehash")]
+    pub fn labelhashes(&self) -> Vec<[u8; 32]> {
+        crate::namehash::labelhashes(&self.labels)
+    }
+
+    /// Number of labels in the name, `0` for the empty name
+    pub fn label_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Validated tokens of all labels as one stream, labels separated by stop tokens.
+    /// Unlike [`ProcessedName::tokenized`], reflects validation, e.g. disallowed policy substitutions.
+    pub fn flat_tokens(&self) -> Vec<EnsNameToken> {
+        let mut tokens = Vec::new();
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                tokens.push(EnsNameToken::stop());
+            }
+            tokens.extend(label.tokens.iter().cloned());
+        }
+        tokens
+    }
+
+    /// Returns an iterator over validated labels together with their indices
+    pub fn labels_iter(&self) -> impl Iterator<Item = (usize, &ValidatedLabel)> {
+        self.labels.iter().enumerate()
+    }
+
+    /// Returns the validated label at `index`, if any
+    pub fn label(&self, index: usize) -> Option<&ValidatedLabel> {
+        self.labels.get(index)
+    }
+
+    /// Returns the slice of the original input the label at `index` came from,
+    /// e.g. `"Ｖi\u{AD}talik"` for label `0` of `"Ｖi\u{AD}talik.eth"`
+    pub fn input_label(&self, index: usize) -> Option<&str> {
+        self.tokenized.input_label(index)
+    }
+
+    /// Returns normalized text, type and restriction flag of every label
+    pub fn label_report(&self) -> Vec<LabelReport> {
+        self.labels
+            .iter()
+            .map(|label| LabelReport {
+                normalized: join_labels(std::slice::from_ref(label)),
+                label_type: label.label_type.clone(),
+                restricted: label.restricted,
+            })
+            .collect()
+    }
+
+    /// Reports labels that are almost whole-script confusable with another group,
+    /// i.e. the confusable group lacks only one of the label's shared code points
+    pub fn confusability_report(&self, specs: &CodePointsSpecs) -> Vec<ConfusabilityNote> {
+        confusability_notes(&self.labels, specs)
+    }
+
+    /// Lossy key for fuzzy-lookup buckets: the normalized name with emoji skin tones stripped
+    /// and every code point replaced by its confusable skeleton, e.g. Greek `α` becomes Latin `a`.
+    ///
+    /// This is NOT the canonical ENS name: it cannot be reversed and must never be used for resolution.
+    pub fn search_key(&self, specs: &CodePointsSpecs) -> String {
+        let mut cps = Vec::new();
+        for token in &self.tokenized.tokens {
+            match token {
+                EnsNameToken::Emoji(emoji) => cps.extend(
+                    emoji
+                        .cps_no_fe0f
+                        .iter()
+                        .filter(|cp| !constants::CP_SKIN_TONE_MODIFIERS.contains(cp)),
+                ),
+                EnsNameToken::Valid(_) | EnsNameToken::Mapped(_) | EnsNameToken::Nfc(_) => {
+                    for cp in token.cps() {
+                        match specs.confusable_target(cp) {
+                            Some(target) => cps.extend(target),
+                            None => cps.push(cp),
+                        }
+                    }
+                }
+                EnsNameToken::Stop(stop) => cps.push(stop.cp),
+                EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => {}
+            }
+        }
+        utils::cps2str(&cps)
+    }
+
+    /// Returns the sorted set of distinct code points of the normalized name, including stops
+    pub fn code_point_set(&self) -> BTreeSet<CodePoint> {
+        self.normalize().chars().map(|c| c as CodePoint).collect()
+    }
+
+    /// Returns true if every label is made of emoji only, e.g. `💩.🔥`. `false` for the empty name.
+    pub fn is_all_emoji(&self) -> bool {
+        !self.labels.is_empty()
+            && self
+                .labels
+                .iter()
+                .all(|label| label.label_type == LabelType::Emoji)
+    }
+
+    /// Returns true if any mapped code point came from the Halfwidth and Fullwidth Forms block,
+    /// e.g. fullwidth `ｖ` mapped to `v`
+    pub fn used_fullwidth(&self) -> bool {
+        self.tokenized.tokens.iter().any(|token| match token {
+            EnsNameToken::Mapped(mapped) => {
+                constants::CP_HALFWIDTH_FULLWIDTH_FORMS.contains(&mapped.cp)
+            }
+            _ => false,
+        })
+    }
+
+    /// Maps each character of the normalized name to the byte range of the original input it came from.
+    /// Characters produced by NFC composition have no single source and are mapped to `None`.
+    pub fn source_map(&self) -> Vec<(char, Option<Range<usize>>)> {
+        let mut result = Vec::new();
+        for (i, token) in self.tokenized.tokens.iter().enumerate() {
+            let span = self.tokenized.spans.get(i).cloned();
+            match token {
+                EnsNameToken::Valid(_) => match &span {
+                    Some(span) => {
+                        result.extend(self.tokenized.input[span.clone()].char_indices().map(
+                            |(offset, c)| {
+                                let start = span.start + offset;
+                                (c, Some(start..start + c.len_utf8()))
+                            },
+                        ))
+                    }
+                    None => result.extend(token.as_string().chars().map(|c| (c, None))),
+                },
+                EnsNameToken::Mapped(_) | EnsNameToken::Emoji(_) | EnsNameToken::Stop(_) => {
+                    result.extend(token.as_string().chars().map(|c| (c, span.clone())))
+                }
+                EnsNameToken::Nfc(_) => result.extend(token.as_string().chars().map(|c| (c, None))),
+                EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => {}
+            }
+        }
+        result
+    }
+
+    /// Explains how the original input turned into the normalized name.
+    /// Built from the tokens: mapped, NFC and emoji tokens are replacements, ignored ones are removals.
+    pub fn input_vs_normalized_diff(&self) -> Vec<DiffOp> {
+        let mut ops: Vec<DiffOp> = Vec::new();
+        for token in &self.tokenized.tokens {
+            let op = match token {
+                EnsNameToken::Valid(_) | EnsNameToken::Stop(_) => DiffOp::Keep(token.as_string()),
+                EnsNameToken::Mapped(mapped) => DiffOp::Replace {
+                    from: utils::cps2str(&[mapped.cp]),
+                    to: token.as_string(),
+                },
+                EnsNameToken::Nfc(nfc) => DiffOp::Replace {
+                    from: utils::cps2str(&nfc.input),
+                    to: token.as_string(),
+                },
+                EnsNameToken::Emoji(emoji) if emoji.cps_input == emoji.cps_no_fe0f => {
+                    DiffOp::Keep(token.as_string())
+                }
+                EnsNameToken::Emoji(emoji) => DiffOp::Replace {
+                    from: emoji.input.clone(),
+                    to: token.as_string(),
+                },
+                EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => {
+                    DiffOp::Remove(token.as_string())
+                }
+            };
+            match (ops.last_mut(), op) {
+                (Some(DiffOp::Keep(last)), DiffOp::Keep(next))
+                | (Some(DiffOp::Remove(last)), DiffOp::Remove(next)) => last.push_str(&next),
+                (_, op) => ops.push(op),
+            }
+        }
+        ops
+    }
+
+    /// Returns true if normalization does not change the original input.
+    /// Checks the tokens instead of building the normalized string.
+    /// `input` differs from the tokenized one only if it was premapped.
+    fn is_input_normalized(&self, input: &str) -> bool {
+        self.tokenized.input == input
+            && self.tokenized.tokens.iter().all(|token| match token {
+                EnsNameToken::Valid(_) | EnsNameToken::Stop(_) => true,
+                EnsNameToken::Emoji(emoji) => emoji.cps_input == emoji.cps_no_fe0f,
+                EnsNameToken::Mapped(_)
+                | EnsNameToken::Ignored(_)
+                | EnsNameToken::Disallowed(_)
+                | EnsNameToken::Nfc(_) => false,
+            })
+    }
+}
+
+/// Replaces every disallowed token in place, keeping spans intact. Returns the number of replaced tokens.
+fn replace_disallowed(name: &mut TokenizedName, f: impl Fn(CodePoint) -> EnsNameToken) -> usize {
+    let mut count = 0;
+    for token in name.tokens.iter_mut() {
+        if let EnsNameToken::Disallowed(disallowed) = token {
+            *token = f(disallowed.cp);
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Upper bound on cure attempts for a label given in its normalized form:
+/// one to normalize it, then one per removable code point, plus one to spare.
+/// Curing stops there even if a fix would not shorten the label.
+fn max_cure_steps(normalized: &str) -> usize {
+    normalized.chars().count() + 2
+}
+
+/// Replaces the sequence of a curable error in a normalized label with its suggestion.
+/// `None` if the error does not point into the label or would not shorten it,
+/// so repeated curing always terminates.
+fn apply_cure(label: &str, err: &ProcessError) -> Option<String> {
+    let ProcessError::CurrableError {
+        index,
+        sequence,
+        maybe_suggest: Some(suggest),
+        ..
+    } = err
+    else {
+        return None;
+    };
+    let mut cps = utils::str2cps(label);
+    let sequence = utils::str2cps(sequence);
+    let suggest = utils::str2cps(suggest);
+    let range = *index..index + sequence.len();
+    if cps.get(range.clone()) != Some(sequence.as_slice()) || suggest.len() >= sequence.len() {
+        return None;
+    }
+    cps.splice(range, suggest);
+    Some(utils::cps2str(&cps))
+}
+
+/// Processes the input as required by ENSIP-15, on top of the given validation options
+pub(crate) fn process_with(
+    input: &str,
+    specs: &CodePointsSpecs,
+    options: &ValidationOptions,
+) -> Result<ProcessedName, ProcessError> {
+    no_control_characters(input)?;
+    let tokenized = TokenizedName::from_input(input, specs, true)?;
+    let labels = validate_name(&tokenized, specs, options)?;
+    Ok(ProcessedName {
+        tokenized,
+        labels,
+        substitutions: 0,
+    })
+}
+
+/// Fails with [`DisallowedSequence::Invalid`] at the first stop, if any
+fn single_label(tokenized: &TokenizedName) -> Result<(), ProcessError> {
+    let mut index = 0;
+    for token in &tokenized.tokens {
+        if token.is_stop() {
+            return Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::Invalid {
+                    sequence: utils::cp2str(constants::CP_STOP),
+                    index,
+                },
+            ));
+        }
+        index += token.input_size();
+    }
+    Ok(())
+}
+
+/// Rejects C0 and C1 control characters before tokenization,
+/// so they are reported explicitly instead of as generic disallowed characters
+fn no_control_characters(input: &str) -> Result<(), ProcessError> {
+    match input.chars().find(|c| c.is_control()) {
+        Some(c) => Err(ProcessError::DisallowedSequence(
+            DisallowedSequence::ControlCharacter(c as CodePoint),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn emoji_insensitive_cps(name: &TokenizedName) -> Vec<CodePoint> {
+    name.tokens
+        .iter()
+        .flat_map(|token| match token {
+            EnsNameToken::Emoji(emoji) => emoji.cps_no_fe0f.clone(),
+            EnsNameToken::Ignored(ignored) if ignored.cp == constants::CP_FE0F => vec![],
+            EnsNameToken::Mapped(mapped) => vec![mapped.cp],
+            EnsNameToken::Nfc(nfc) => nfc.input.clone(),
+            EnsNameToken::Valid(_)
+            | EnsNameToken::Ignored(_)
+            | EnsNameToken::Disallowed(_)
+            | EnsNameToken::Stop(_) => token.cps(),
+        })
+        .collect()
 }
 
 /// `no-cache` version of [`EnsNameNormalizer::tokenize`]
@@ -78,3 +1116,46 @@ pub fn normalize(input: impl AsRef<str>) -> Result<String, ProcessError> {
 pub fn beautify(input: impl AsRef<str>) -> Result<String, ProcessError> {
     EnsNameNormalizer::default().beautify(input)
 }
+
+/// Every emoji supported by the bundled spec, fully-qualified as [`beautify`] writes them,
+/// see [`CodePointsSpecs::emoji_iter`]
+pub fn ens_emoji() -> Vec<String> {
+    BUNDLED_SPECS
+        .emoji_iter()
+        .map(|emoji| utils::cps2str(emoji))
+        .collect()
+}
+
+/// `no-cache` version of [`EnsNameNormalizer::labelhash`]
+#[cfg(feature = "namehash")]
+pub fn labelhash(label: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+    EnsNameNormalizer::default().labelhash(label)
+}
+
+/// `no-cache` version of [`EnsNameNormalizer::namehash`]
+#[cfg(feature = "namehash")]
+pub fn namehash(input: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+    EnsNameNormalizer::default().namehash(input)
+}
+
+/// `no-cache` version of [`EnsNameNormalizer::namehash_dns`]
+#[cfg(feature = "namehash")]
+pub fn namehash_dns(input: impl AsRef<str>) -> Result<[u8; 32], ProcessError> {
+    EnsNameNormalizer::default().namehash_dns(input)
+}
+
+/// `no-cache` version of [`EnsNameNormalizer::collision_explanation`]
+pub fn collision_explanation(
+    a: impl AsRef<str>,
+    b: impl AsRef<str>,
+) -> Result<Vec<(usize, CollisionReason)>, ProcessError> {
+    EnsNameNormalizer::default().collision_explanation(a, b)
+}
+
+/// `no-cache` version of [`EnsNameNormalizer::names_emoji_equivalent`]
+pub fn names_emoji_equivalent(
+    a: impl AsRef<str>,
+    b: impl AsRef<str>,
+) -> Result<bool, ProcessError> {
+    EnsNameNormalizer::default().names_emoji_equivalent(a, b)
+}
@@ -0,0 +1,151 @@
+use crate::{utils, CodePointsSpecs, ConfusabilityNote, EnsNameToken, LabelType, ProcessedName};
+
+/// Everything known about a processed name, see [`crate::EnsNameNormalizer::normalize_with_report`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizationReport {
+    pub normalized: String,
+    pub beautified: String,
+    pub label_types: Vec<LabelType>,
+    /// Changes made to the input, in input order
+    pub transforms: Vec<Transform>,
+    /// Properties of a valid name worth a second look
+    pub warnings: Vec<Warning>,
+}
+
+/// One change made to the input during normalization
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transform {
+    /// Code point position in the input
+    pub index: usize,
+    pub kind: TransformKind,
+    pub from: String,
+    /// Empty for removed characters
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformKind {
+    /// Mapped by the spec, e.g. `A` to `a`
+    Mapped,
+    /// Removed as ignored, e.g. a soft hyphen
+    Ignored,
+    /// Composed by NFC, e.g. `e` followed by `U+0301` to `é`
+    Nfc,
+    /// `FE0F` stripped from an emoji
+    Emoji,
+    /// Disallowed character dropped by [`crate::DisallowedPolicy::Remove`]
+    Removed,
+    /// Disallowed character substituted by [`crate::DisallowedPolicy::Replace`]
+    Replaced,
+}
+
+/// Valid but suspicious properties of a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// Label is almost whole-script confusable with another group
+    NearlyConfusable(ConfusabilityNote),
+    /// Label belongs to a restricted group
+    Restricted {
+        label_index: usize,
+        label_type: LabelType,
+    },
+    /// Label renders as blank or as floating marks
+    Degenerate { label_index: usize },
+    /// Disallowed characters were removed or replaced, so the name is not the input's normalized form
+    Substituted { count: usize },
+}
+
+pub(crate) fn build_report(
+    processed: &ProcessedName,
+    specs: &CodePointsSpecs,
+) -> NormalizationReport {
+    NormalizationReport {
+        normalized: processed.normalize(),
+        beautified: processed.beautify(),
+        label_types: processed
+            .labels
+            .iter()
+            .map(|label| label.label_type.clone())
+            .collect(),
+        transforms: transforms(processed, specs),
+        warnings: warnings(processed, specs),
+    }
+}
+
+fn transforms(processed: &ProcessedName, specs: &CodePointsSpecs) -> Vec<Transform> {
+    let mut transforms = Vec::new();
+    let mut index = 0;
+    for token in &processed.tokenized.tokens {
+        let transform = match token {
+            // processed names never keep disallowed tokens, see `DisallowedPolicy`
+            EnsNameToken::Valid(_) | EnsNameToken::Stop(_) | EnsNameToken::Disallowed(_) => None,
+            // substituted disallowed characters are neither mapped nor ignored by the spec
+            EnsNameToken::Mapped(mapped) => Some((
+                if specs.is_mapped(mapped.cp) {
+                    TransformKind::Mapped
+                } else {
+                    TransformKind::Replaced
+                },
+                utils::cp2str(mapped.cp),
+                utils::cps2str(&mapped.cps),
+            )),
+            EnsNameToken::Ignored(ignored) => Some((
+                if specs.is_ignored(ignored.cp) {
+                    TransformKind::Ignored
+                } else {
+                    TransformKind::Removed
+                },
+                token.as_string(),
+                String::new(),
+            )),
+            EnsNameToken::Nfc(nfc) => Some((
+                TransformKind::Nfc,
+                utils::cps2str(&nfc.input),
+                utils::cps2str(&nfc.cps),
+            )),
+            EnsNameToken::Emoji(emoji) if emoji.cps_input != emoji.cps_no_fe0f => Some((
+                TransformKind::Emoji,
+                emoji.input.clone(),
+                utils::cps2str(&emoji.cps_no_fe0f),
+            )),
+            EnsNameToken::Emoji(_) => None,
+        };
+        if let Some((kind, from, to)) = transform {
+            transforms.push(Transform {
+                index,
+                kind,
+                from,
+                to,
+            });
+        }
+        index += token.input_size();
+    }
+    transforms
+}
+
+fn warnings(processed: &ProcessedName, specs: &CodePointsSpecs) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (label_index, label) in processed.labels_iter() {
+        if label.restricted {
+            warnings.push(Warning::Restricted {
+                label_index,
+                label_type: label.label_type.clone(),
+            });
+        }
+        if label.is_degenerate(specs) {
+            warnings.push(Warning::Degenerate { label_index });
+        }
+    }
+    warnings.extend(
+        processed
+            .confusability_report(specs)
+            .into_iter()
+            .map(Warning::NearlyConfusable),
+    );
+    if processed.substitutions > 0 {
+        warnings.push(Warning::Substituted {
+            count: processed.substitutions,
+        });
+    }
+    warnings
+}
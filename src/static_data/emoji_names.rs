@@ -0,0 +1,32 @@
+use crate::{utils::filter_fe0f, CodePoint};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// CLDR short names in the format written by `tools/emoji-names.py` from Unicode's `emoji-test.txt`.
+/// Only a subset of the spec's emoji is listed so far, rerun the script to get the full table.
+const EMOJI_NAMES_CONTENT: &str = include_str!("emoji_names.json");
+
+lazy_static! {
+    /// CLDR short names keyed by emoji code points without `FE0F`
+    pub static ref EMOJI_NAMES: HashMap<Vec<CodePoint>, String> = {
+        let table: EmojiNames = serde_json::from_str(EMOJI_NAMES_CONTENT)
+            .expect("bundled emoji_names.json is valid");
+        table
+            .names
+            .into_iter()
+            .map(|entry| (filter_fe0f(&entry.emoji), entry.name))
+            .collect()
+    };
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmojiNames {
+    pub names: Vec<EmojiName>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmojiName {
+    pub emoji: Vec<CodePoint>,
+    pub name: String,
+}
@@ -1,2 +1,4 @@
+#[cfg(feature = "emoji-names")]
+pub mod emoji_names;
 pub mod nf_json;
 pub mod spec_json;
@@ -24,6 +24,13 @@ pub struct DecompItem {
     pub nested_numbers: Vec<CodePoint>,
 }
 
+impl Nf {
+    /// Parses the bundled `nf.json` without panicking
+    pub fn try_bundled() -> Result<Self, serde_json::Error> {
+        serde_json::from_str(NF_CONTENT)
+    }
+}
+
 impl Default for Nf {
     fn default() -> Self {
         DEFAULT_NF.clone()
@@ -29,6 +29,7 @@ pub struct Spec {
     pub ignored: Vec<CodePoint>,
     pub mapped: Vec<Mapped>,
     pub fenced: Vec<Fenced>,
+    pub wholes: Vec<Whole>,
     pub cm: Vec<CodePoint>,
     pub nsm: Vec<CodePoint>,
     pub nsm_max: u32,
@@ -52,8 +53,8 @@ pub struct Fenced {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Whole {
-    #[serde(deserialize_with = "serde_aux::prelude::deserialize_number_from_string")]
-    pub target: CodePoint,
+    /// Hex code points of the confusable skeleton separated by spaces, e.g. `"306 307"`
+    pub target: String,
     pub valid: Vec<CodePoint>,
     pub confused: Vec<CodePoint>,
 }
@@ -81,6 +82,38 @@ pub enum GroupName {
 derive_fromstr_from_deserialize!(GroupName);
 derive_display_from_serialize!(GroupName);
 
+impl GroupName {
+    /// Script of the group, `None` for `Emoji`, `ASCII` and groups without a [`Script`] variant
+    pub fn script(&self) -> Option<Script> {
+        match self {
+            GroupName::Greek => Some(Script::Greek),
+            GroupName::Other(name) => name.parse().ok(),
+            GroupName::Emoji | GroupName::Ascii => None,
+        }
+    }
+}
+
+/// Common scripts among spec group names, see [`GroupName::script`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Hash)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Han,
+    Japanese,
+    Korean,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Bengali,
+    Tamil,
+    Thai,
+    Armenian,
+    Georgian,
+}
+derive_fromstr_from_deserialize!(Script);
+derive_display_from_serialize!(Script);
+
 impl From<String> for GroupName {
     fn from(s: String) -> Self {
         s.parse::<Self>().unwrap_or(Self::Other(s))
@@ -93,6 +126,13 @@ impl GroupName {
     }
 }
 
+impl Spec {
+    /// Parses the bundled `spec.json` without panicking
+    pub fn try_bundled() -> Result<Self, serde_json::Error> {
+        serde_json::from_str(SPEC_CONTENT)
+    }
+}
+
 impl Default for Spec {
     fn default() -> Self {
         DEFAULT_SPEC.clone()
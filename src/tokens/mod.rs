@@ -1,5 +1,6 @@
 mod tokenize;
 mod types;
 
+pub(crate) use tokenize::process_one_cp;
 pub use tokenize::{TokenizedLabel, TokenizedName};
 pub use types::*;
@@ -1,16 +1,19 @@
 use crate::{
+    join,
     tokens::{
-        CollapsedEnsNameToken, EnsNameToken, TokenDisallowed, TokenEmoji, TokenIgnored,
+        CollapsedEnsNameToken, EnsNameToken, Run, TokenDisallowed, TokenEmoji, TokenIgnored,
         TokenMapped, TokenNfc, TokenStop, TokenValid,
     },
     utils, CodePoint, CodePointsSpecs, ProcessError,
 };
+use std::ops::Range;
 
 /// Represents a full ENS name, including the original input and the sequence of tokens
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TokenizedName {
     pub input: String,
     pub tokens: Vec<EnsNameToken>,
+    pub(crate) spans: Vec<Range<usize>>,
 }
 
 /// Represents a tokenized ENS label (part of a name separated by periods), including sequence of tokens
@@ -24,6 +27,7 @@ impl TokenizedName {
         Self {
             input: "".to_string(),
             tokens: vec![],
+            spans: vec![],
         }
     }
 
@@ -36,10 +40,35 @@ impl TokenizedName {
         tokenize_name(input, specs, apply_nfc)
     }
 
+    /// Builds a tokenized name from custom tokens, e.g. after filtering or mapping
+    /// tokens of another name. Adjacent valid tokens are collapsed, `spans` is left empty.
+    pub fn from_tokens(
+        input: impl Into<String>,
+        tokens: impl IntoIterator<Item = EnsNameToken>,
+    ) -> Self {
+        let mut tokens = tokens.into_iter().collect();
+        collapse_valid_tokens(&mut tokens, &mut vec![]);
+        Self {
+            input: input.into(),
+            tokens,
+            spans: vec![],
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.tokens.is_empty()
     }
 
+    /// Returns the length of the original input in bytes.
+    pub fn input_byte_len(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Returns the number of code points in the original input.
+    pub fn input_cp_len(&self) -> usize {
+        self.input.chars().count()
+    }
+
     /// Returns an iterator over all tokens in the tokenized name.
     pub fn iter_tokens(&self) -> impl Iterator<Item = &EnsNameToken> {
         self.tokens.iter()
@@ -56,6 +85,43 @@ impl TokenizedName {
     pub fn labels(&self) -> Vec<TokenizedLabel<'_>> {
         self.iter_labels().collect()
     }
+
+    /// Joins the tokens into a normalized-looking string, emoji without `FE0F`.
+    ///
+    /// Does NOT perform any ENSIP-15 validation: disallowed characters are silently dropped
+    /// and invalid labels are joined as is. Only use it for names already known to be valid,
+    /// otherwise call [`crate::EnsNameNormalizer::normalize`].
+    pub fn normalized_unchecked(&self) -> String {
+        join::join_tokens(&self.tokens)
+    }
+
+    /// Byte range of the original input covered by each token, empty if
+    /// tokens were not produced from the input directly, see [`TokenizedName::from_tokens`]
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// Returns the slice of the original input covered by the label at `index`,
+    /// including ignored, mapped and disallowed characters. Labels around leading, trailing
+    /// or consecutive stops are empty slices. `None` if there is no such label
+    /// or spans were not recorded, see [`TokenizedName::from_tokens`].
+    pub fn input_label(&self, index: usize) -> Option<&str> {
+        if self.spans.len() != self.tokens.len() {
+            return None;
+        }
+        let mut stops = self
+            .tokens
+            .iter()
+            .zip(&self.spans)
+            .filter(|(token, _)| token.is_stop())
+            .map(|(_, span)| span);
+        let mut start = 0;
+        for _ in 0..index {
+            start = stops.next()?.end;
+        }
+        let end = stops.next().map_or(self.input.len(), |span| span.start);
+        self.input.get(start..end)
+    }
 }
 
 impl TokenizedLabel<'_> {
@@ -108,6 +174,15 @@ impl TokenizedLabel<'_> {
         collapsed
     }
 
+    /// Splits the label into contiguous text and emoji runs, preserving order.
+    /// Unlike [`Self::collapse_into_text_or_emoji`], every run carries its rendered text.
+    pub fn runs(&self) -> Vec<Run> {
+        self.collapse_into_text_or_emoji()
+            .into_iter()
+            .map(Run::from)
+            .collect()
+    }
+
     /// Returns a vector of codepoints from all text tokens, excluding emoji and ignored tokens
     pub fn get_cps_of_not_ignored_text(&self) -> Vec<CodePoint> {
         self.collapse_into_text_or_emoji()
@@ -144,10 +219,11 @@ fn tokenize_name(
     if name.is_empty() {
         return Ok(TokenizedName::empty());
     }
-    let tokens = tokenize_input(name, specs, apply_nfc)?;
+    let (tokens, spans) = tokenize_input(name, specs, apply_nfc)?;
     Ok(TokenizedName {
         input: name.to_string(),
         tokens,
+        spans,
     })
 }
 
@@ -155,39 +231,48 @@ fn tokenize_input(
     input: impl AsRef<str>,
     specs: &CodePointsSpecs,
     apply_nfc: bool,
-) -> Result<Vec<EnsNameToken>, ProcessError> {
+) -> Result<(Vec<EnsNameToken>, Vec<Range<usize>>), ProcessError> {
     let input = input.as_ref();
     let emojis = specs.finditer_emoji(input).collect::<Vec<_>>();
 
     let mut tokens = Vec::new();
+    let mut spans = Vec::new();
     let mut input_cur = 0;
 
     while input_cur < input.len() {
         if let Some(emoji) = maybe_starts_with_emoji(input_cur, input, &emojis, specs) {
             let cursor_offset = emoji.input.len();
             tokens.push(EnsNameToken::Emoji(emoji));
+            spans.push(input_cur..input_cur + cursor_offset);
             input_cur += cursor_offset;
         } else {
-            let char = input[input_cur..]
-                .chars()
-                .next()
-                .expect("input_cur is in bounds");
+            // `get` instead of indexing: never panic if the cursor is not on a char boundary
+            let Some(char) = input.get(input_cur..).and_then(|rest| rest.chars().next()) else {
+                return Err(ProcessError::Internal(format!(
+                    "tokenizer cursor at byte {input_cur} is not on a char boundary"
+                )));
+            };
             let cursor_offset = char.len_utf8();
             let cp = char as CodePoint;
             let token = process_one_cp(cp, specs);
             tokens.push(token);
+            spans.push(input_cur..input_cur + cursor_offset);
             input_cur += cursor_offset;
         }
     }
 
     if apply_nfc {
-        perform_nfc_transform(&mut tokens, specs);
+        perform_nfc_transform(&mut tokens, &mut spans, specs);
     }
-    collapse_valid_tokens(&mut tokens);
-    Ok(tokens)
+    collapse_valid_tokens(&mut tokens, &mut spans);
+    Ok((tokens, spans))
 }
 
-fn perform_nfc_transform(tokens: &mut Vec<EnsNameToken>, specs: &CodePointsSpecs) {
+fn perform_nfc_transform(
+    tokens: &mut Vec<EnsNameToken>,
+    spans: &mut Vec<Range<usize>>,
+    specs: &CodePointsSpecs,
+) {
     let mut i = 0;
     let mut start = -1i32;
 
@@ -227,7 +312,11 @@ fn perform_nfc_transform(tokens: &mut Vec<EnsNameToken>, specs: &CodePointsSpecs
                     }
 
                     let str0 = utils::cps2str(&cps);
-                    let str = utils::nfc(&str0);
+                    let str = if specs.nfc_quick_check(&cps) {
+                        str0.clone()
+                    } else {
+                        utils::nfc(&str0)
+                    };
 
                     if str0 == str {
                         i = end - 1;
@@ -237,6 +326,8 @@ fn perform_nfc_transform(tokens: &mut Vec<EnsNameToken>, specs: &CodePointsSpecs
                             cps: utils::str2cps(&str),
                         });
                         tokens.splice(start as usize..end, vec![new_token]);
+                        let span = spans[start as usize].start..spans[end - 1].end;
+                        spans.splice(start as usize..end, vec![span]);
                         i = start as usize;
                     }
                     start = -1;
@@ -264,66 +355,59 @@ fn maybe_starts_with_emoji(
     emojis.iter().find_map(|emoji| {
         let start = emoji.start();
         if start == i {
-            let end = emoji.end();
-            let input_cps = utils::str2cps(&label[start..end]);
-            let cps_no_fe0f = utils::filter_fe0f(&input_cps);
-            let emoji = specs
-                .cps_emoji_no_fe0f_to_pretty(&cps_no_fe0f)
-                .expect("emoji should be found")
-                .clone();
-            Some(TokenEmoji {
-                input: label[start..end].to_string(),
-                cps_input: input_cps,
-                emoji,
-                cps_no_fe0f,
-            })
+            // regex matches over `&str` lie on char boundaries, checked anyway to never panic
+            TokenEmoji::from_matched_input(label.get(start..emoji.end())?, specs)
         } else {
             None
         }
     })
 }
 
-fn process_one_cp(cp: CodePoint, specs: &CodePointsSpecs) -> EnsNameToken {
+pub(crate) fn process_one_cp(cp: CodePoint, specs: &CodePointsSpecs) -> EnsNameToken {
     if specs.is_stop(cp) {
         EnsNameToken::Stop(TokenStop { cp })
     } else if specs.is_valid(cp) {
         EnsNameToken::Valid(TokenValid { cps: vec![cp] })
     } else if specs.is_ignored(cp) {
         EnsNameToken::Ignored(TokenIgnored { cp })
-    } else if let Some(normalized) = specs.maybe_normalize(cp) {
-        EnsNameToken::Mapped(TokenMapped {
-            cp,
-            cps: normalized.clone(),
-        })
+    } else if let Some(mapped) = TokenMapped::new(cp, specs) {
+        EnsNameToken::Mapped(mapped)
     } else {
         EnsNameToken::Disallowed(TokenDisallowed { cp })
     }
 }
 
-fn collapse_valid_tokens(tokens: &mut Vec<EnsNameToken>) {
-    let mut i = 0;
-    while i < tokens.len() {
-        if let EnsNameToken::Valid(token) = &tokens[i] {
-            let mut j = i + 1;
-            let mut cps = token.cps.clone();
-            while j < tokens.len() {
-                if let EnsNameToken::Valid(next_token) = &tokens[j] {
-                    cps.extend(next_token.cps.iter());
-                    j += 1;
-                } else {
-                    break;
+fn collapse_valid_tokens(tokens: &mut Vec<EnsNameToken>, spans: &mut Vec<Range<usize>>) {
+    let with_spans = !spans.is_empty();
+    let mut collapsed_tokens: Vec<EnsNameToken> = Vec::with_capacity(tokens.len());
+    let mut collapsed_spans: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    for (i, token) in std::mem::take(tokens).into_iter().enumerate() {
+        if let (EnsNameToken::Valid(next), Some(EnsNameToken::Valid(last))) =
+            (&token, collapsed_tokens.last_mut())
+        {
+            last.cps.extend_from_slice(&next.cps);
+            if with_spans {
+                if let Some(span) = collapsed_spans.last_mut() {
+                    span.end = spans[i].end;
                 }
             }
-            let new_token = EnsNameToken::Valid(TokenValid { cps });
-            tokens.splice(i..j, vec![new_token].into_iter());
+            continue;
         }
-        i += 1;
+        collapsed_tokens.push(token);
+        if with_spans {
+            collapsed_spans.push(spans[i].clone());
+        }
+    }
+    *tokens = collapsed_tokens;
+    if with_spans {
+        *spans = collapsed_spans;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokens::RunKind;
     use pretty_assertions::assert_eq;
     use rstest::{fixture, rstest};
 
@@ -373,8 +457,156 @@ mod tests {
         #[case] expected: Vec<EnsNameToken>,
     ) {
         let mut tokens = input;
-        collapse_valid_tokens(&mut tokens);
+        let mut spans = (0..tokens.len()).map(|i| i..i + 1).collect();
+        collapse_valid_tokens(&mut tokens, &mut spans);
         assert_eq!(tokens, expected);
+        assert_eq!(spans.len(), tokens.len());
+    }
+
+    #[rstest]
+    fn test_collapse_valid_tokens_spans() {
+        let mut tokens = vec![
+            EnsNameToken::Valid(TokenValid { cps: vec![1] }),
+            EnsNameToken::Valid(TokenValid { cps: vec![2] }),
+            EnsNameToken::Ignored(TokenIgnored { cp: 3 }),
+            EnsNameToken::Valid(TokenValid { cps: vec![4] }),
+            EnsNameToken::Valid(TokenValid { cps: vec![5] }),
+            EnsNameToken::Valid(TokenValid { cps: vec![6] }),
+        ];
+        let mut spans = vec![0..1, 1..3, 3..5, 5..6, 6..8, 8..9];
+        collapse_valid_tokens(&mut tokens, &mut spans);
+        assert_eq!(spans, vec![0..3, 3..5, 5..9]);
+        assert_eq!(
+            tokens,
+            vec![
+                EnsNameToken::Valid(TokenValid { cps: vec![1, 2] }),
+                EnsNameToken::Ignored(TokenIgnored { cp: 3 }),
+                EnsNameToken::Valid(TokenValid { cps: vec![4, 5, 6] }),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::valid("Vitalik.eth", "vitalik.eth")]
+    #[case::emoji("💩\u{FE0F}.ETH", "💩.eth")]
+    #[case::ignored("vi\u{AD}talik", "vitalik")]
+    #[case::invalid_not_checked("a..b_c-", "a..b_c-")]
+    fn test_normalized_unchecked(
+        #[case] input: &str,
+        #[case] expected: &str,
+        specs: &CodePointsSpecs,
+    ) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        assert_eq!(name.normalized_unchecked(), expected);
+    }
+
+    #[rstest]
+    #[case::adjacent_emoji("💩👨\u{200D}💻🇺🇸💩\u{FE0F}", 4)]
+    #[case::emoji_and_combining("é\u{301}💩\u{303}1\u{20E3}ξ\u{300}", 2)]
+    #[case::keycaps_and_flags("1\u{FE0F}\u{20E3}🇺🇸#\u{20E3}\u{303}", 3)]
+    #[case::broken_sequences("👨\u{200D}\u{200D}🇺\u{FE0F}\u{FE0F}", 1)]
+    fn test_multibyte_token_boundaries(
+        #[case] input: &str,
+        #[case] expected_emoji: usize,
+        specs: &CodePointsSpecs,
+    ) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        assert_eq!(name.spans.len(), name.tokens.len());
+        let mut cursor = 0;
+        for span in &name.spans {
+            assert_eq!(span.start, cursor);
+            assert!(input.is_char_boundary(span.start) && input.is_char_boundary(span.end));
+            cursor = span.end;
+        }
+        assert_eq!(cursor, input.len());
+        for (token, span) in name.tokens.iter().zip(&name.spans) {
+            if let EnsNameToken::Emoji(emoji) = token {
+                assert_eq!(emoji.input, input[span.clone()]);
+            }
+        }
+        let emoji = name.tokens.iter().filter(|token| token.is_emoji()).count();
+        assert_eq!(emoji, expected_emoji);
+    }
+
+    #[rstest]
+    fn test_multibyte_token_spans(specs: &CodePointsSpecs) {
+        // 4-byte emoji, ZWJ sequence, NFC composed pair, keycap and combining mark after emoji
+        let input = "💩👨\u{200D}💻e\u{301}1\u{FE0F}\u{20E3}\u{303}";
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        let actual = name
+            .tokens
+            .iter()
+            .zip(&name.spans)
+            .map(|(token, span)| (token.as_string(), span.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                ("💩".to_string(), 0..4),
+                ("👨\u{200D}💻".to_string(), 4..15),
+                ("é".to_string(), 15..18),
+                ("1\u{20E3}".to_string(), 18..25),
+                ("\u{303}".to_string(), 25..27),
+            ]
+        );
+        assert!(name.tokens[0].is_emoji() && name.tokens[1].is_emoji());
+        assert!(name.tokens[2].is_nfc());
+        assert!(name.tokens[3].is_emoji() && !name.tokens[4].is_emoji());
+    }
+
+    #[rstest]
+    #[case::single("abc", vec!["abc"])]
+    #[case::mapped("A\u{AD}B.\u{FF25}TH", vec!["A\u{AD}B", "\u{FF25}TH"])]
+    #[case::empty("", vec![""])]
+    #[case::leading_stop(".eth", vec!["", "eth"])]
+    #[case::trailing_stop("eth.", vec!["eth", ""])]
+    #[case::consecutive_stops("a..b", vec!["a", "", "b"])]
+    fn test_input_label(#[case] input: &str, #[case] expected: Vec<&str>, specs: &CodePointsSpecs) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        let labels = (0..expected.len())
+            .map(|i| name.input_label(i).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, expected);
+        assert_eq!(name.input_label(expected.len()), None);
+        assert_eq!(name.iter_labels().count(), expected.len());
+    }
+
+    #[rstest]
+    fn test_from_tokens(specs: &CodePointsSpecs) {
+        let original = TokenizedName::from_input("a\u{AD}bc.eth", specs, true).unwrap();
+        let tokens = original
+            .tokens
+            .into_iter()
+            .filter(|token| !token.is_ignored());
+        let name = TokenizedName::from_tokens("a\u{AD}bc.eth", tokens);
+        assert_eq!(
+            name.tokens,
+            vec![
+                EnsNameToken::Valid(TokenValid {
+                    cps: vec![97, 98, 99]
+                }),
+                EnsNameToken::Stop(TokenStop { cp: 46 }),
+                EnsNameToken::Valid(TokenValid {
+                    cps: vec![101, 116, 104]
+                }),
+            ]
+        );
+        assert!(name.spans.is_empty());
+    }
+
+    #[rstest]
+    #[case::empty("", 0, 0)]
+    #[case::ascii("vitalik.eth", 11, 11)]
+    #[case::emoji("🚴\u{200d}♂\u{fe0f}.eth", 17, 8)]
+    fn test_input_len(
+        #[case] input: &str,
+        #[case] byte_len: usize,
+        #[case] cp_len: usize,
+        specs: &CodePointsSpecs,
+    ) {
+        let tokenized = TokenizedName::from_input(input, specs, true).unwrap();
+        assert_eq!(tokenized.input_byte_len(), byte_len);
+        assert_eq!(tokenized.input_cp_len(), cp_len);
     }
 
     #[rstest]
@@ -462,8 +694,10 @@ mod tests {
         #[case] expected: Vec<EnsNameToken>,
         specs: &CodePointsSpecs,
     ) {
-        let tokens = tokenize_input(input, specs, apply_nfc).expect("tokenize");
+        let (tokens, spans) = tokenize_input(input, specs, apply_nfc).expect("tokenize");
         assert_eq!(tokens, expected);
+        assert_eq!(spans.len(), tokens.len());
+        assert_eq!(spans.last().map(|span| span.end), Some(input.len()));
     }
 
     #[rstest]
@@ -486,9 +720,37 @@ mod tests {
         #[case] expected: Vec<CollapsedEnsNameToken>,
         specs: &CodePointsSpecs,
     ) {
-        let tokens = tokenize_input(input, specs, true).expect("tokenize");
+        let (tokens, _) = tokenize_input(input, specs, true).expect("tokenize");
         let label = TokenizedLabel::from(&tokens);
         let result = label.collapse_into_text_or_emoji();
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case::text_only("abc", vec![(RunKind::Text, "abc")])]
+    #[case::mixed("a💩Ab👍🏻", vec![
+        (RunKind::Text, "a"),
+        (RunKind::Emoji, "💩\u{FE0F}"),
+        (RunKind::Text, "ab"),
+        (RunKind::Emoji, "👍🏻"),
+    ])]
+    #[case::beautified_emoji("❤x", vec![(RunKind::Emoji, "❤️"), (RunKind::Text, "x")])]
+    #[case::ignored_between("a\u{AD}b", vec![(RunKind::Text, "ab")])]
+    #[case::empty("", vec![])]
+    fn test_runs(
+        #[case] input: &str,
+        #[case] expected: Vec<(RunKind, &str)>,
+        specs: &CodePointsSpecs,
+    ) {
+        let (tokens, _) = tokenize_input(input, specs, true).expect("tokenize");
+        let runs = TokenizedLabel::from(&tokens).runs();
+        for run in &runs {
+            assert_eq!(run.cps, utils::str2cps(&run.text));
+        }
+        let actual = runs
+            .iter()
+            .map(|run| (run.kind, run.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
 }
@@ -1,8 +1,10 @@
-use crate::{constants, utils, CodePoint};
+use crate::{constants, utils, CodePoint, CodePointsSpecs};
+use serde::Serialize;
 
 /// Represents a token in an ENS name.
 /// see <https://docs.ens.domains/ensip/15#tokenize> for more details.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum EnsNameToken {
     Valid(TokenValid),
     Mapped(TokenMapped),
@@ -26,6 +28,7 @@ impl EnsNameToken {
         }
     }
 
+    /// Number of code points of the original input covered by the token
     pub fn input_size(&self) -> usize {
         match self {
             EnsNameToken::Valid(t) => t.cps.len(),
@@ -38,6 +41,35 @@ impl EnsNameToken {
         }
     }
 
+    /// Number of UTF-8 bytes the token contributes to the normalized name,
+    /// `0` for ignored and disallowed tokens. Emoji are counted without `FE0F`, as in [`crate::join_labels`].
+    pub fn output_byte_len(&self) -> usize {
+        match self {
+            EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) => 0,
+            _ => self
+                .cps()
+                .into_iter()
+                .filter_map(char::from_u32)
+                .map(char::len_utf8)
+                .sum(),
+        }
+    }
+
+    /// Returns true if any input code point of the token is in the spec's `escape` set,
+    /// so it should be rendered escaped even if it is valid or ignored.
+    /// Emoji are never escaped, their `FE0F` and `ZWJ` are part of the sequence.
+    pub fn needs_escape(&self, specs: &CodePointsSpecs) -> bool {
+        match self {
+            EnsNameToken::Valid(t) => t.cps.iter().any(|cp| specs.needs_escape(*cp)),
+            EnsNameToken::Nfc(t) => t.input.iter().any(|cp| specs.needs_escape(*cp)),
+            EnsNameToken::Mapped(TokenMapped { cp, .. })
+            | EnsNameToken::Ignored(TokenIgnored { cp })
+            | EnsNameToken::Disallowed(TokenDisallowed { cp })
+            | EnsNameToken::Stop(TokenStop { cp }) => specs.needs_escape(*cp),
+            EnsNameToken::Emoji(_) => false,
+        }
+    }
+
     pub fn is_text(&self) -> bool {
         matches!(
             self,
@@ -45,6 +77,18 @@ impl EnsNameToken {
         )
     }
 
+    pub fn is_valid(&self) -> bool {
+        matches!(self, EnsNameToken::Valid(_))
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, EnsNameToken::Mapped(_))
+    }
+
+    pub fn is_nfc(&self) -> bool {
+        matches!(self, EnsNameToken::Nfc(_))
+    }
+
     pub fn is_emoji(&self) -> bool {
         matches!(self, EnsNameToken::Emoji(_))
     }
@@ -70,41 +114,82 @@ impl EnsNameToken {
     pub fn as_string(&self) -> String {
         utils::cps2str(&self.cps())
     }
+
+    /// Human-readable representation of the token with hex code points,
+    /// e.g. `Valid("abc" [61 62 63])` or `Mapped("A" [41] -> "a" [61])`
+    pub fn debug_pretty(&self) -> String {
+        match self {
+            EnsNameToken::Valid(t) => format!("Valid({})", pretty_cps(&t.cps)),
+            EnsNameToken::Mapped(t) => {
+                format!("Mapped({} -> {})", pretty_cps(&[t.cp]), pretty_cps(&t.cps))
+            }
+            EnsNameToken::Ignored(t) => format!("Ignored({})", pretty_cps(&[t.cp])),
+            EnsNameToken::Disallowed(t) => format!("Disallowed({})", pretty_cps(&[t.cp])),
+            EnsNameToken::Stop(t) => format!("Stop({})", pretty_cps(&[t.cp])),
+            EnsNameToken::Nfc(t) => {
+                format!("Nfc({} -> {})", pretty_cps(&t.input), pretty_cps(&t.cps))
+            }
+            EnsNameToken::Emoji(t) => format!(
+                "Emoji({} -> {})",
+                pretty_cps(&t.cps_input),
+                pretty_cps(&t.cps_no_fe0f)
+            ),
+        }
+    }
+}
+
+fn pretty_cps(cps: &[CodePoint]) -> String {
+    let hex = cps
+        .iter()
+        .map(|cp| format!("{:X}", cp))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{:?} [{}]", utils::cps2str(cps), hex)
 }
 
 /// A valid vector of code points
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenValid {
     pub cps: Vec<CodePoint>,
 }
 
 /// Code point should be mapped to vector of code points
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenMapped {
     pub cps: Vec<CodePoint>,
     pub cp: CodePoint,
 }
 
+impl TokenMapped {
+    /// Builds a mapped token for `cp`, returns `None` if the spec has no mapping for it
+    pub fn new(cp: CodePoint, specs: &CodePointsSpecs) -> Option<Self> {
+        specs.maybe_normalize(cp).map(|cps| Self {
+            cp,
+            cps: cps.clone(),
+        })
+    }
+}
+
 /// Code point should be ignored
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenIgnored {
     pub cp: CodePoint,
 }
 
 /// Code point is disallowed
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenDisallowed {
     pub cp: CodePoint,
 }
 
 /// Represents a stop token (.)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenStop {
     pub cp: CodePoint,
 }
 
 /// Represents a vector of code points that should be normalized using NFC
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenNfc {
     pub cps: Vec<CodePoint>,
     pub input: Vec<CodePoint>,
@@ -114,7 +199,7 @@ pub struct TokenNfc {
 /// `cps_input` contains vector of code from input string
 /// `emoji` contains vector of beautified emoji code points
 /// `cps_no_fe0f` contains vector of code points of emoji without `FE0F`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenEmoji {
     pub input: String,
     pub emoji: Vec<CodePoint>,
@@ -122,6 +207,40 @@ pub struct TokenEmoji {
     pub cps_no_fe0f: Vec<CodePoint>,
 }
 
+impl TokenEmoji {
+    /// Builds an emoji token from its input, computing all derived code points.
+    /// Returns `None` if the input is not exactly one emoji of the spec.
+    pub fn new(input: &str, specs: &CodePointsSpecs) -> Option<Self> {
+        let cps_input = utils::str2cps(input);
+        if !specs.cps_is_emoji(&cps_input) {
+            return None;
+        }
+        Self::from_matched_input(input, specs)
+    }
+
+    /// Same as [`TokenEmoji::new`] for input already matched by the emoji regex
+    pub(crate) fn from_matched_input(input: &str, specs: &CodePointsSpecs) -> Option<Self> {
+        let cps_input = utils::str2cps(input);
+        let cps_no_fe0f = utils::filter_fe0f(&cps_input);
+        let emoji = specs.cps_emoji_no_fe0f_to_pretty(&cps_no_fe0f)?.clone();
+        Some(Self {
+            input: input.to_string(),
+            cps_input,
+            emoji,
+            cps_no_fe0f,
+        })
+    }
+
+    /// CLDR short name of the emoji, e.g. `"person biking"` for `🚴`.
+    /// Matched without `FE0F`, `None` for emoji missing from the bundled table.
+    #[cfg(feature = "emoji-names")]
+    pub fn short_name(&self) -> Option<&str> {
+        crate::static_data::emoji_names::EMOJI_NAMES
+            .get(&self.cps_no_fe0f)
+            .map(String::as_str)
+    }
+}
+
 /// Represents a collapsed token in an ENS name: either text or emoji
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CollapsedEnsNameToken {
@@ -129,7 +248,44 @@ pub enum CollapsedEnsNameToken {
     Emoji(TokenEmoji),
 }
 
+/// Kind of a contiguous [`Run`] of a label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunKind {
+    Text,
+    Emoji,
+}
+
+/// Contiguous run of text or a single emoji, ready to be rendered.
+/// Emoji runs use the beautified (fully-qualified) emoji code points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub kind: RunKind,
+    pub text: String,
+    pub cps: Vec<CodePoint>,
+}
+
+impl Run {
+    fn new(kind: RunKind, cps: Vec<CodePoint>) -> Self {
+        Self {
+            kind,
+            text: utils::cps2str(&cps),
+            cps,
+        }
+    }
+}
+
+impl From<CollapsedEnsNameToken> for Run {
+    fn from(token: CollapsedEnsNameToken) -> Self {
+        match token {
+            CollapsedEnsNameToken::Text(text) => Run::new(RunKind::Text, text.cps),
+            CollapsedEnsNameToken::Emoji(emoji) => Run::new(RunKind::Emoji, emoji.emoji),
+        }
+    }
+}
+
 impl CollapsedEnsNameToken {
+    /// Number of code points in the token. Text is collapsed from normalized code points,
+    /// so unlike [`EnsNameToken::input_size`] it does not match the original input length.
     pub fn input_size(&self) -> usize {
         match self {
             CollapsedEnsNameToken::Text(t) => t.cps.len(),
@@ -137,3 +293,179 @@ impl CollapsedEnsNameToken {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    #[once]
+    fn specs() -> CodePointsSpecs {
+        CodePointsSpecs::default()
+    }
+
+    #[rstest]
+    #[case::without_fe0f("💩", Some((vec![0x1F4A9], vec![0x1F4A9, 0xFE0F])))]
+    #[case::with_fe0f("💩\u{FE0F}", Some((vec![0x1F4A9], vec![0x1F4A9, 0xFE0F])))]
+    #[case::zwj(
+        "👨\u{200D}💻",
+        Some((vec![0x1F468, 0x200D, 0x1F4BB], vec![0x1F468, 0x200D, 0x1F4BB]))
+    )]
+    #[case::two_emoji("💩💩", None)]
+    #[case::text("a", None)]
+    #[case::empty("", None)]
+    fn test_token_emoji_new(
+        #[case] input: &str,
+        #[case] expected: Option<(Vec<CodePoint>, Vec<CodePoint>)>,
+        specs: &CodePointsSpecs,
+    ) {
+        let token = TokenEmoji::new(input, specs);
+        if let Some(token) = &token {
+            assert_eq!(token.input, input);
+            assert_eq!(token.cps_input, utils::str2cps(input));
+        }
+        assert_eq!(
+            token.map(|token| (token.cps_no_fe0f, token.emoji)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::upper('A', Some(vec![0x61]))]
+    #[case::trade_mark('™', Some(vec![0x74, 0x6D]))]
+    #[case::valid('a', None)]
+    fn test_token_mapped_new(
+        #[case] c: char,
+        #[case] expected: Option<Vec<CodePoint>>,
+        specs: &CodePointsSpecs,
+    ) {
+        let token = TokenMapped::new(c as CodePoint, specs);
+        assert_eq!(token.map(|token| token.cps), expected);
+    }
+
+    #[rstest]
+    #[case::valid(
+        EnsNameToken::Valid(TokenValid { cps: vec![0x61, 0x62, 0x63] }),
+        r#"Valid("abc" [61 62 63])"#
+    )]
+    #[case::mapped(
+        EnsNameToken::Mapped(TokenMapped { cp: 0x41, cps: vec![0x61] }),
+        r#"Mapped("A" [41] -> "a" [61])"#
+    )]
+    #[case::ignored(
+        EnsNameToken::Ignored(TokenIgnored { cp: 0xAD }),
+        r#"Ignored("\u{ad}" [AD])"#
+    )]
+    #[case::emoji(
+        EnsNameToken::Emoji(TokenEmoji { input: "💩\u{fe0f}".to_string(), cps_input: vec![0x1F4A9, 0xFE0F], emoji: vec![0x1F4A9, 0xFE0F], cps_no_fe0f: vec![0x1F4A9] }),
+        r#"Emoji("💩\u{fe0f}" [1F4A9 FE0F] -> "💩" [1F4A9])"#
+    )]
+    fn test_debug_pretty(#[case] token: EnsNameToken, #[case] expected: &str) {
+        assert_eq!(token.debug_pretty(), expected);
+    }
+
+    #[rstest]
+    #[case::mapped(
+        EnsNameToken::Mapped(TokenMapped { cp: 0x41, cps: vec![0x61] }),
+        serde_json::json!({"type": "mapped", "cp": 65, "cps": [97]})
+    )]
+    #[case::stop(
+        EnsNameToken::stop(),
+        serde_json::json!({"type": "stop", "cp": 46})
+    )]
+    fn test_serialize(#[case] token: EnsNameToken, #[case] expected: serde_json::Value) {
+        assert_eq!(serde_json::to_value(&token).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_predicates(specs: &CodePointsSpecs) {
+        let name = crate::TokenizedName::from_input("aB\u{AD}e\u{301}💩.!", specs, true)
+            .expect("tokenize");
+        let kinds = name
+            .tokens
+            .iter()
+            .map(|token| {
+                [
+                    token.is_valid(),
+                    token.is_mapped(),
+                    token.is_ignored(),
+                    token.is_nfc(),
+                    token.is_emoji(),
+                    token.is_stop(),
+                    token.is_disallowed(),
+                ]
+                .iter()
+                .position(|is| *is)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6)
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::valid("abc", vec![false])]
+    #[case::ignored_soft_hyphen("a\u{AD}b", vec![false, true, false])]
+    #[case::disallowed_zwnj("a\u{200C}", vec![false, true])]
+    #[case::emoji_zwj("👨‍💻", vec![false])]
+    fn test_needs_escape(
+        #[case] input: &str,
+        #[case] expected: Vec<bool>,
+        specs: &CodePointsSpecs,
+    ) {
+        let name = crate::TokenizedName::from_input(input, specs, true).expect("tokenize");
+        let actual = name
+            .tokens
+            .iter()
+            .map(|token| token.needs_escape(specs))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case::ascii("vitalik.eth")]
+    #[case::mapped_and_ignored("Vit\u{AD}alik.eth")]
+    #[case::nfc("cafe\u{301}.eth")]
+    #[case::emoji_with_fe0f("❤️🏳️‍🌈.eth")]
+    #[case::empty("")]
+    fn test_output_byte_len(#[case] input: &str, specs: &CodePointsSpecs) {
+        let name = crate::TokenizedName::from_input(input, specs, true).expect("tokenize");
+        let total: usize = name.tokens.iter().map(EnsNameToken::output_byte_len).sum();
+        let normalized = crate::normalize(input).expect("name should be valid");
+        assert_eq!(total, normalized.len());
+    }
+
+    #[cfg(feature = "emoji-names")]
+    #[rstest]
+    #[case::single("🚴", Some("person biking"))]
+    #[case::without_fe0f("❤", Some("red heart"))]
+    #[case::with_fe0f("❤\u{FE0F}", Some("red heart"))]
+    #[case::zwj_sequence("👨\u{200D}💻", Some("man technologist"))]
+    fn test_emoji_short_name(
+        #[case] input: &str,
+        #[case] expected: Option<&str>,
+        specs: &CodePointsSpecs,
+    ) {
+        let emoji = TokenEmoji::new(input, specs).unwrap();
+        assert_eq!(emoji.short_name(), expected);
+    }
+
+    #[cfg(feature = "emoji-names")]
+    #[rstest]
+    fn test_emoji_names_are_spec_emoji(specs: &CodePointsSpecs) {
+        for cps in crate::static_data::emoji_names::EMOJI_NAMES.keys() {
+            assert!(specs.cps_is_emoji(cps), "{cps:X?} is not an emoji");
+        }
+    }
+}
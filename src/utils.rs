@@ -3,6 +3,7 @@ use unicode_normalization::UnicodeNormalization;
 
 const FE0F: CodePoint = 0xfe0f;
 const LAST_ASCII_CP: CodePoint = 0x7f;
+const DOTTED_CIRCLE: char = '\u{25CC}';
 
 #[inline]
 pub fn filter_fe0f(cps: &[CodePoint]) -> Vec<CodePoint> {
@@ -36,15 +37,101 @@ pub fn nfc(str: &str) -> String {
     str.nfc().collect()
 }
 
-#[inline]
+/// Renders untrusted code points, e.g. the `sequence` of a [`crate::ProcessError`], safely:
+/// code points that [`CodePointsSpecs::should_escape`] are written as `{HEX}`,
+/// and a leading combining mark is put on a dotted circle `◌`.
+pub fn safe_display(cps: &[CodePoint], specs: &CodePointsSpecs) -> String {
+    let mut display = String::new();
+    if cps.first().is_some_and(|cp| specs.is_cm(*cp)) {
+        display.push(DOTTED_CIRCLE);
+    }
+    for cp in cps {
+        match char::from_u32(*cp) {
+            Some(c) if !specs.should_escape(*cp) => display.push(c),
+            _ => display.push_str(&format!("{{{:02X}}}", cp)),
+        }
+    }
+    display
+}
+
+/// Full canonical decomposition of code points followed by canonical ordering of combining marks
 pub fn nfd_cps(cps: &[CodePoint], specs: &CodePointsSpecs) -> Vec<CodePoint> {
     let mut decomposed = Vec::new();
     for cp in cps {
-        if let Some(decomposed_cp) = specs.decompose(*cp) {
-            decomposed.extend(decomposed_cp);
-        } else {
-            decomposed.push(*cp);
-        }
+        decompose_into(*cp, specs, &mut decomposed);
     }
+    canonical_order(&mut decomposed, specs);
     decomposed
 }
+
+fn decompose_into(cp: CodePoint, specs: &CodePointsSpecs, decomposed: &mut Vec<CodePoint>) {
+    match specs.decompose(cp) {
+        Some(decomposed_cps) => {
+            for cp in decomposed_cps {
+                decompose_into(*cp, specs, decomposed);
+            }
+        }
+        None => decomposed.push(cp),
+    }
+}
+
+/// Stable sort of every run of combining marks by their combining class rank
+fn canonical_order(cps: &mut [CodePoint], specs: &CodePointsSpecs) {
+    let mut start = 0;
+    while start < cps.len() {
+        if specs.combining_rank(cps[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let end = cps[start..]
+            .iter()
+            .position(|cp| specs.combining_rank(*cp) == 0)
+            .map_or(cps.len(), |len| start + len);
+        cps[start..end].sort_by_key(|cp| specs.combining_rank(*cp));
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    #[once]
+    fn specs() -> CodePointsSpecs {
+        CodePointsSpecs::default()
+    }
+
+    #[rstest]
+    #[case::plain("vitalik", "vitalik")]
+    #[case::control("a\0b\n", "a{00}b{0A}")]
+    #[case::bidi_override("\u{202E}abc", "{202E}abc")]
+    #[case::invisible("a\u{200C}b", "a{200C}b")]
+    #[case::leading_cm("\u{301}a", "\u{25CC}\u{301}a")]
+    #[case::inner_cm("a\u{301}", "a\u{301}")]
+    #[case::empty("", "")]
+    fn test_safe_display(#[case] input: &str, #[case] expected: &str, specs: &CodePointsSpecs) {
+        assert_eq!(safe_display(&str2cps(input), specs), expected);
+    }
+
+    #[rstest]
+    #[case::no_marks(&[0x61, 0x62], &[0x61, 0x62])]
+    #[case::single_level(&[0xC0], &[0x41, 0x300])]
+    #[case::recursive(&[0x1D6], &[0x75, 0x308, 0x304])]
+    // acute (230) must go after dot below (220)
+    #[case::reordered(&[0x61, 0x301, 0x323], &[0x61, 0x323, 0x301])]
+    #[case::same_class_keeps_order(&[0x61, 0x301, 0x300], &[0x61, 0x301, 0x300])]
+    #[case::runs_are_independent(
+        &[0x61, 0x301, 0x323, 0x62, 0x301, 0x323],
+        &[0x61, 0x323, 0x301, 0x62, 0x323, 0x301]
+    )]
+    fn test_nfd_cps(
+        #[case] cps: &[CodePoint],
+        #[case] expected: &[CodePoint],
+        specs: &CodePointsSpecs,
+    ) {
+        assert_eq!(nfd_cps(cps, specs), expected);
+    }
+}
@@ -4,62 +4,393 @@ use crate::{
     TokenizedLabel, TokenizedName,
 };
 use itertools::Itertools;
-use std::collections::HashSet;
 pub type LabelType = spec_json::GroupName;
 
-/// Represents a validated ENS label as result of the `validate_label` function.
+/// Represents a validated ENS label as result of the `validate_name` function.
 /// Contains the original tokenized label and the type of the label.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValidatedLabel {
     pub tokens: Vec<EnsNameToken>,
     pub label_type: LabelType,
+    /// Whether the label's group is restricted, always `false` for ASCII and emoji labels
+    pub restricted: bool,
+}
+
+impl ValidatedLabel {
+    /// Returns a borrowed [`TokenizedLabel`] view of the label tokens
+    pub fn as_label(&self) -> TokenizedLabel<'_> {
+        TokenizedLabel::from(&self.tokens)
+    }
+
+    /// Returns true if the label would render as blank or as floating marks:
+    /// it has no emoji and its non-ignored text is empty or consists only of combining marks
+    pub fn is_degenerate(&self, specs: &CodePointsSpecs) -> bool {
+        let collapsed = self.as_label().collapse_into_text_or_emoji();
+        if collapsed
+            .iter()
+            .any(|token| matches!(token, CollapsedEnsNameToken::Emoji(_)))
+        {
+            return false;
+        }
+        collapsed.iter().all(|token| match token {
+            CollapsedEnsNameToken::Text(text) => text.cps.iter().all(|cp| specs.is_cm(*cp)),
+            CollapsedEnsNameToken::Emoji(_) => false,
+        })
+    }
+}
+
+/// Same as [`ValidatedLabel`], but borrows the tokens from the tokenized name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatedLabelRef<'a> {
+    pub tokens: &'a [EnsNameToken],
+    pub label_type: LabelType,
+    /// Whether the label's group is restricted, always `false` for ASCII and emoji labels
+    pub restricted: bool,
+}
+
+impl<'a> ValidatedLabelRef<'a> {
+    /// Returns a borrowed [`TokenizedLabel`] view of the label tokens
+    pub fn as_label(&self) -> TokenizedLabel<'a> {
+        TokenizedLabel::from(self.tokens)
+    }
+
+    /// Clones the tokens into an owned [`ValidatedLabel`]
+    pub fn into_owned(self) -> ValidatedLabel {
+        ValidatedLabel {
+            tokens: self.tokens.to_vec(),
+            label_type: self.label_type,
+            restricted: self.restricted,
+        }
+    }
+}
+
+/// Additional restrictions applied on top of ENSIP-15 validation.
+/// All of them are disabled by default.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationOptions {
+    /// Minimal number of code points in a normalized label, `0` means no limit
+    pub min_label_length: usize,
+    /// Reject labels starting with an underscore, which ENSIP-15 allows
+    pub forbid_leading_underscore: bool,
+    /// Which emoji are accepted
+    pub emoji_policy: EmojiPolicy,
+    /// Maximal number of identical emoji in a row, `None` means no limit
+    pub max_consecutive_emoji: Option<usize>,
+    /// Maximal number of labels in a name, `None` means no limit
+    pub max_labels: Option<usize>,
+    /// Reject names whose input was not already in NFC form
+    pub require_nfc: bool,
+}
+
+/// Restricts emoji allowed in names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmojiPolicy {
+    /// Any emoji from the spec, as ENSIP-15 does
+    #[default]
+    All,
+    /// Reject emoji sequences joined with ZWJ (U+200D)
+    NoZwj,
+    /// Reject all emoji
+    None,
 }
 
 pub fn validate_name(
     name: &TokenizedName,
     specs: &CodePointsSpecs,
+    options: &ValidationOptions,
 ) -> Result<Vec<ValidatedLabel>, ProcessError> {
+    let labels = validate_name_ref(name, specs, options)?;
+    Ok(labels
+        .into_iter()
+        .map(ValidatedLabelRef::into_owned)
+        .collect())
+}
+
+/// Same as [`validate_name`], but the validated labels borrow tokens from `name`
+pub fn validate_name_ref<'a>(
+    name: &'a TokenizedName,
+    specs: &CodePointsSpecs,
+    options: &ValidationOptions,
+) -> Result<Vec<ValidatedLabelRef<'a>>, ProcessError> {
+    match validate_name_partial(name, specs, options) {
+        (labels, None) => Ok(labels),
+        (_, Some(err)) => Err(err),
+    }
+}
+
+/// Validates labels one by one until the first failure.
+/// Returns the labels validated before the failing one together with the error.
+pub fn validate_name_partial<'a>(
+    name: &'a TokenizedName,
+    specs: &CodePointsSpecs,
+    options: &ValidationOptions,
+) -> (Vec<ValidatedLabelRef<'a>>, Option<ProcessError>) {
+    if name.is_empty() {
+        return (vec![], None);
+    }
+    if let Some(max) = options.max_labels {
+        let count = name.iter_labels().count();
+        if count > max {
+            return (
+                vec![],
+                Some(ProcessError::DisallowedSequence(
+                    DisallowedSequence::TooManyLabels { count, max },
+                )),
+            );
+        }
+    }
+    if options.require_nfc {
+        if let Err(err) = check_nfc(name) {
+            return (vec![], Some(err));
+        }
+    }
+    let mut labels = vec![];
+    for (label_index, label) in name.iter_labels().enumerate() {
+        match validate_label_with_options(label_index, label, specs, options) {
+            Ok(label) => labels.push(label),
+            Err(err) => return (labels, Some(err)),
+        }
+    }
+    (labels, None)
+}
+
+/// Validates every label regardless of failures in the others, returning a result
+/// per label index. Name-wide options, i.e. `max_labels` and `require_nfc`, are not checked.
+pub fn validate_name_all(
+    name: &TokenizedName,
+    specs: &CodePointsSpecs,
+    options: &ValidationOptions,
+) -> Vec<(usize, Result<ValidatedLabel, ProcessError>)> {
     if name.is_empty() {
-        return Ok(vec![]);
+        return vec![];
+    }
+    name.iter_labels()
+        .enumerate()
+        .map(|(label_index, label)| {
+            let result = validate_label_with_options(label_index, label, specs, options)
+                .map(ValidatedLabelRef::into_owned);
+            (label_index, result)
+        })
+        .collect()
+}
+
+/// Fails on the first token changed by NFC composition,
+/// `index` is the position of its first code point in the input
+fn check_nfc(name: &TokenizedName) -> Result<(), ProcessError> {
+    let mut index = 0;
+    for token in &name.tokens {
+        if token.is_nfc() {
+            return Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::NotNfc { index },
+            ));
+        }
+        index += token.input_size();
+    }
+    Ok(())
+}
+
+fn validate_label_with_options<'a>(
+    label_index: usize,
+    label: TokenizedLabel<'a>,
+    specs: &CodePointsSpecs,
+    options: &ValidationOptions,
+) -> Result<ValidatedLabelRef<'a>, ProcessError> {
+    let validated = validate_label(label.clone(), specs)?;
+    check_label_length(label_index, &label, options)?;
+    if options.forbid_leading_underscore {
+        no_leading_underscore(&label)?;
     }
-    let labels = name
-        .iter_labels()
-        .map(|label| validate_label(label, specs))
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(labels)
+    check_emoji_policy(&label, options.emoji_policy)?;
+    if let Some(max) = options.max_consecutive_emoji {
+        check_consecutive_emoji(&label, max)?;
+    }
+    Ok(validated)
 }
 
 /// Validates a tokenized ENS label according to the ENSIP 15 specification
 /// https://docs.ens.domains/ensip/15#validate
-pub fn validate_label(
-    label: TokenizedLabel<'_>,
+pub fn validate_label<'a>(
+    label: TokenizedLabel<'a>,
     specs: &CodePointsSpecs,
-) -> Result<ValidatedLabel, ProcessError> {
+) -> Result<ValidatedLabelRef<'a>, ProcessError> {
     non_empty(&label)?;
     check_token_types(&label)?;
     if label.is_fully_emoji() {
-        return Ok(ValidatedLabel {
-            tokens: label.tokens.to_owned(),
+        return Ok(ValidatedLabelRef {
+            tokens: label.tokens,
             label_type: LabelType::Emoji,
+            restricted: false,
         });
     };
     underscore_only_at_beginning(&label)?;
     if label.is_fully_ascii() {
         no_hyphen_at_second_and_third(&label)?;
-        return Ok(ValidatedLabel {
-            tokens: label.tokens.to_owned(),
+        return Ok(ValidatedLabelRef {
+            tokens: label.tokens,
             label_type: LabelType::Ascii,
+            restricted: false,
         });
     }
     check_fenced(&label, specs)?;
     check_cm_leading_emoji(&label, specs)?;
     let group = check_and_get_group(&label, specs)?;
-    Ok(ValidatedLabel {
-        tokens: label.tokens.to_owned(),
-        label_type: group.name,
+    Ok(ValidatedLabelRef {
+        tokens: label.tokens,
+        label_type: group.name.clone(),
+        restricted: group.restricted,
     })
 }
 
+/// Validates labels assumed to belong to one group, skipping group resolution.
+/// Built by [`CodePointsSpecs::validator_for_group`] for homogeneous bulk workloads.
+pub struct LabelValidator<'a> {
+    specs: &'a CodePointsSpecs,
+    group: &'a ParsedGroup,
+}
+
+impl<'a> LabelValidator<'a> {
+    pub(crate) fn new(specs: &'a CodePointsSpecs, group: &'a ParsedGroup) -> Self {
+        Self { specs, group }
+    }
+
+    /// Group every validated label is checked against
+    pub fn group(&self) -> &'a ParsedGroup {
+        self.group
+    }
+
+    /// Tokenizes and validates a single label. Runs the same checks as [`validate_label`],
+    /// but instead of resolving the group requires every code point to be in the validator's group.
+    /// The label type is always the validator's group, even for ASCII labels.
+    pub fn validate(&self, input: impl AsRef<str>) -> Result<ValidatedLabel, ProcessError> {
+        let name = TokenizedName::from_input(input, self.specs, true)?;
+        self.validate_label(TokenizedLabel::from(&name.tokens))
+            .map(ValidatedLabelRef::into_owned)
+    }
+
+    /// Same as [`LabelValidator::validate`] for an already tokenized label
+    pub fn validate_label<'t>(
+        &self,
+        label: TokenizedLabel<'t>,
+    ) -> Result<ValidatedLabelRef<'t>, ProcessError> {
+        let specs = self.specs;
+        non_empty(&label)?;
+        check_token_types(&label)?;
+        underscore_only_at_beginning(&label)?;
+        if label.is_fully_ascii() {
+            no_hyphen_at_second_and_third(&label)?;
+        }
+        check_fenced(&label, specs)?;
+        check_cm_leading_emoji(&label, specs)?;
+        let cps = label.get_cps_of_not_ignored_text();
+        if let Some(cp) = cps
+            .iter()
+            .find(|cp| !self.group.primary_plus_secondary.contains(cp))
+        {
+            return Err(ProcessError::Confused(format!(
+                "{} is not in group {}",
+                specs.describe_cp(*cp),
+                self.group.name
+            )));
+        }
+        let unique_cps = cps.iter().cloned().unique().collect::<Vec<_>>();
+        check_group(self.group, &cps, specs)?;
+        check_whole(self.group, &unique_cps, specs)?;
+        Ok(ValidatedLabelRef {
+            tokens: label.tokens,
+            label_type: self.group.name.clone(),
+            restricted: self.group.restricted,
+        })
+    }
+}
+
+fn check_label_length(
+    label_index: usize,
+    label: &TokenizedLabel,
+    options: &ValidationOptions,
+) -> Result<(), ProcessError> {
+    let len = label
+        .tokens
+        .iter()
+        .map(|token| match token {
+            EnsNameToken::Valid(_)
+            | EnsNameToken::Mapped(_)
+            | EnsNameToken::Nfc(_)
+            | EnsNameToken::Emoji(_) => token.cps().len(),
+            EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) | EnsNameToken::Stop(_) => 0,
+        })
+        .sum::<usize>();
+    if len < options.min_label_length {
+        return Err(ProcessError::DisallowedSequence(
+            DisallowedSequence::LabelTooShort { label_index, len },
+        ));
+    }
+    Ok(())
+}
+
+fn no_leading_underscore(label: &TokenizedLabel) -> Result<(), ProcessError> {
+    let leading_underscores = label
+        .iter_cps()
+        .take_while(|cp| *cp == constants::CP_UNDERSCORE)
+        .count();
+    if leading_underscores > 0 {
+        return Err(ProcessError::CurrableError {
+            inner: CurrableError::LeadingUnderscoreForbidden,
+            index: 0,
+            sequence: utils::cps2str(&vec![constants::CP_UNDERSCORE; leading_underscores]),
+            maybe_suggest: Some("".to_string()),
+        });
+    }
+    Ok(())
+}
+
+fn check_emoji_policy(label: &TokenizedLabel, policy: EmojiPolicy) -> Result<(), ProcessError> {
+    let rejected = label.tokens.iter().find_map(|token| match token {
+        EnsNameToken::Emoji(emoji) => {
+            let allowed = match policy {
+                EmojiPolicy::All => true,
+                EmojiPolicy::NoZwj => !emoji.cps_no_fe0f.contains(&constants::CP_ZERO_WIDTH_JOINER),
+                EmojiPolicy::None => false,
+            };
+            (!allowed).then(|| emoji.cps_no_fe0f.clone())
+        }
+        _ => None,
+    });
+    match rejected {
+        Some(cps) => Err(ProcessError::DisallowedSequence(
+            DisallowedSequence::EmojiNotAllowed { cps },
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check_consecutive_emoji(label: &TokenizedLabel, max: usize) -> Result<(), ProcessError> {
+    let mut previous: Option<&Vec<CodePoint>> = None;
+    let mut count = 0;
+    for token in label.tokens {
+        match token {
+            EnsNameToken::Emoji(emoji) => {
+                if previous == Some(&emoji.cps_no_fe0f) {
+                    count += 1;
+                } else {
+                    previous = Some(&emoji.cps_no_fe0f);
+                    count = 1;
+                }
+                if count > max {
+                    return Err(ProcessError::DisallowedSequence(
+                        DisallowedSequence::TooManyConsecutiveEmoji { count },
+                    ));
+                }
+            }
+            EnsNameToken::Ignored(_) => {}
+            _ => {
+                previous = None;
+                count = 0;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn non_empty(label: &TokenizedLabel) -> Result<(), ProcessError> {
     let non_ignored_token_exists = label.tokens.iter().any(|token| !token.is_ignored());
     if !non_ignored_token_exists {
@@ -71,12 +402,17 @@ fn non_empty(label: &TokenizedLabel) -> Result<(), ProcessError> {
 }
 
 fn check_token_types(label: &TokenizedLabel) -> Result<(), ProcessError> {
-    if let Some(token) = label
+    if let Some(i) = label
         .tokens
         .iter()
-        .find(|token| token.is_disallowed() || token.is_stop())
+        .position(|token| token.is_disallowed() || token.is_stop())
     {
-        let cps = token.cps();
+        if let Some(cps) = unknown_emoji_around(label.tokens, i) {
+            return Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::UnknownEmoji { cps },
+            ));
+        }
+        let cps = label.tokens[i].cps();
         let maybe_invisible_cp = cps.iter().find(|cp| {
             *cp == &constants::CP_ZERO_WIDTH_JOINER || *cp == &constants::CP_ZERO_WIDTH_NON_JOINER
         });
@@ -85,14 +421,63 @@ fn check_token_types(label: &TokenizedLabel) -> Result<(), ProcessError> {
                 DisallowedSequence::InvisibleCharacter(*invisible_cp),
             ));
         } else {
+            // report the whole run of disallowed characters, e.g. `@@@` in `a@@@b`
+            let run = label.tokens[i..]
+                .iter()
+                .take_while(|token| token.is_disallowed())
+                .flat_map(EnsNameToken::cps)
+                .collect::<Vec<_>>();
+            let sequence = if run.is_empty() { cps } else { run };
+            let index = label.tokens[..i].iter().map(EnsNameToken::input_size).sum();
             return Err(ProcessError::DisallowedSequence(
-                DisallowedSequence::Invalid(utils::cps2str(&cps)),
+                DisallowedSequence::Invalid {
+                    sequence: utils::cps2str(&sequence),
+                    index,
+                },
             ));
         }
     }
     Ok(())
 }
 
+/// Detects an emoji sequence missing from the spec around the disallowed token at `i`:
+/// a `ZWJ` next to an emoji, or a disallowed code point followed by `FE0F`.
+/// Returns input code points of the whole attempted sequence.
+fn unknown_emoji_around(tokens: &[EnsNameToken], i: usize) -> Option<Vec<CodePoint>> {
+    let is_fe0f = |token: &EnsNameToken| matches!(token, EnsNameToken::Ignored(ignored) if ignored.cp == constants::CP_FE0F);
+    let is_part =
+        |token: &EnsNameToken| token.is_emoji() || token.is_disallowed() || is_fe0f(token);
+    let EnsNameToken::Disallowed(disallowed) = &tokens[i] else {
+        return None;
+    };
+    let next = tokens.get(i + 1);
+    let attempted = if disallowed.cp == constants::CP_ZERO_WIDTH_JOINER {
+        let previous = i.checked_sub(1).map(|j| &tokens[j]);
+        previous.is_some_and(EnsNameToken::is_emoji) || next.is_some_and(EnsNameToken::is_emoji)
+    } else {
+        next.is_some_and(is_fe0f)
+    };
+    if !attempted {
+        return None;
+    }
+    let start = tokens[..i]
+        .iter()
+        .rposition(|token| !is_part(token))
+        .map_or(0, |j| j + 1);
+    let end = tokens[i..]
+        .iter()
+        .position(|token| !is_part(token))
+        .map_or(tokens.len(), |j| i + j);
+    let cps = tokens[start..end]
+        .iter()
+        .flat_map(|token| match token {
+            EnsNameToken::Emoji(emoji) => emoji.cps_input.clone(),
+            _ => token.cps(),
+        })
+        .collect();
+    Some(cps)
+}
+
 fn underscore_only_at_beginning(label: &TokenizedLabel) -> Result<(), ProcessError> {
     let leading_underscores = label
         .iter_cps()
@@ -133,9 +518,11 @@ fn no_hyphen_at_second_and_third(label: &TokenizedLabel) -> Result<(), ProcessEr
 
 fn check_fenced(label: &TokenizedLabel, specs: &CodePointsSpecs) -> Result<(), ProcessError> {
     if let Some(first_cp) = label.iter_cps().next() {
-        if specs.is_fenced(first_cp) {
+        if let Some(name) = specs.fenced_info(first_cp) {
             return Err(ProcessError::CurrableError {
-                inner: CurrableError::FencedLeading,
+                inner: CurrableError::FencedLeading {
+                    name: name.to_string(),
+                },
                 index: 0,
                 sequence: utils::cps2str(&[first_cp]),
                 maybe_suggest: Some("".to_string()),
@@ -143,9 +530,11 @@ fn check_fenced(label: &TokenizedLabel, specs: &CodePointsSpecs) -> Result<(), P
         }
     }
     if let Some(last_cp) = label.iter_cps().last() {
-        if specs.is_fenced(last_cp) {
+        if let Some(name) = specs.fenced_info(last_cp) {
             return Err(ProcessError::CurrableError {
-                inner: CurrableError::FencedTrailing,
+                inner: CurrableError::FencedTrailing {
+                    name: name.to_string(),
+                },
                 index: label.iter_cps().count() - 1,
                 sequence: utils::cps2str(&[last_cp]),
                 maybe_suggest: Some("".to_string()),
@@ -155,9 +544,11 @@ fn check_fenced(label: &TokenizedLabel, specs: &CodePointsSpecs) -> Result<(), P
 
     for (i, window) in label.iter_cps().tuple_windows().enumerate() {
         let (one, two) = window;
-        if specs.is_fenced(one) && specs.is_fenced(two) {
+        if let (Some(name), true) = (specs.fenced_info(one), specs.is_fenced(two)) {
             return Err(ProcessError::CurrableError {
-                inner: CurrableError::FencedConsecutive,
+                inner: CurrableError::FencedConsecutive {
+                    name: name.to_string(),
+                },
                 index: i,
                 sequence: utils::cps2str(&[one, two]),
                 maybe_suggest: Some(utils::cp2str(one)),
@@ -167,33 +558,39 @@ fn check_fenced(label: &TokenizedLabel, specs: &CodePointsSpecs) -> Result<(), P
     Ok(())
 }
 
+/// Reports a combining mark at the start of the label or right after an emoji.
+/// `index` is counted in code points of the original input, including ignored ones.
 fn check_cm_leading_emoji(
     label: &TokenizedLabel,
     specs: &CodePointsSpecs,
 ) -> Result<(), ProcessError> {
     let mut index = 0;
-    let collapsed = label.collapse_into_text_or_emoji();
-    for (i, token) in collapsed.iter().enumerate() {
-        if let CollapsedEnsNameToken::Text(token) = token {
-            if let Some(cp) = token.cps.first() {
-                if specs.is_cm(*cp) {
-                    if i == 0 {
-                        return Err(ProcessError::CurrableError {
-                            inner: CurrableError::CmStart,
-                            index,
-                            sequence: utils::cps2str(&[*cp]),
-                            maybe_suggest: Some("".to_string()),
-                        });
-                    } else {
-                        return Err(ProcessError::CurrableError {
-                            inner: CurrableError::CmAfterEmoji,
-                            index,
-                            sequence: utils::cps2str(&[*cp]),
-                            maybe_suggest: Some("".to_string()),
-                        });
-                    }
+    // whether the last non-ignored token was an emoji, `None` at the start of the label
+    let mut after_emoji: Option<bool> = None;
+    for token in label.tokens {
+        match token {
+            EnsNameToken::Emoji(_) => after_emoji = Some(true),
+            EnsNameToken::Valid(_) | EnsNameToken::Mapped(_) | EnsNameToken::Nfc(_) => {
+                let starts_text = after_emoji != Some(false);
+                if let Some(cp) = token
+                    .cps()
+                    .first()
+                    .filter(|cp| starts_text && specs.is_cm(**cp))
+                {
+                    let inner = match after_emoji {
+                        None => CurrableError::CmStart,
+                        Some(_) => CurrableError::CmAfterEmoji,
+                    };
+                    return Err(ProcessError::CurrableError {
+                        inner,
+                        index,
+                        sequence: utils::cps2str(&[*cp]),
+                        maybe_suggest: Some("".to_string()),
+                    });
                 }
+                after_emoji = Some(false);
             }
+            EnsNameToken::Ignored(_) | EnsNameToken::Disallowed(_) | EnsNameToken::Stop(_) => {}
         }
         index += token.input_size();
     }
@@ -201,37 +598,42 @@ fn check_cm_leading_emoji(
     Ok(())
 }
 
-fn check_and_get_group(
+fn check_and_get_group<'a>(
     label: &TokenizedLabel,
-    specs: &CodePointsSpecs,
-) -> Result<ParsedGroup, ProcessError> {
+    specs: &'a CodePointsSpecs,
+) -> Result<&'a ParsedGroup, ProcessError> {
     let cps = label.get_cps_of_not_ignored_text();
-    let unique_cps = cps
-        .clone()
-        .into_iter()
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .collect::<Vec<_>>();
-    let group = determine_group(&unique_cps, specs).cloned()?;
-    check_group(&group, &cps, specs)?;
-    check_whole(&group, &unique_cps, specs)?;
+    let unique_cps = cps.iter().cloned().unique().collect::<Vec<_>>();
+    // the resolved group contains all code points of the label
+    let group =
+        specs.resolve_group_described(&unique_cps, |cp| describe_label_cp(label, cp, specs))?;
+    check_group(group, &cps, specs)?;
+    check_whole(group, &unique_cps, specs)?;
     Ok(group)
 }
 
+/// Same as [`CodePointsSpecs::describe_cp`], but code points produced by a mapping
+/// are attributed to their input, e.g. `U+0061 (Basic Latin) mapped from U+0041 (Basic Latin)`
+fn describe_label_cp(label: &TokenizedLabel, cp: CodePoint, specs: &CodePointsSpecs) -> String {
+    let mapped_from = label.tokens.iter().find_map(|token| match token {
+        EnsNameToken::Mapped(mapped) if mapped.cps.contains(&cp) => Some(mapped.cp),
+        _ => None,
+    });
+    match mapped_from {
+        Some(from) => format!(
+            "{} mapped from {}",
+            specs.describe_cp(cp),
+            specs.describe_cp(from)
+        ),
+        None => specs.describe_cp(cp),
+    }
+}
+
 fn check_group(
     group: &ParsedGroup,
     cps: &[CodePoint],
     specs: &CodePointsSpecs,
 ) -> Result<(), ProcessError> {
-    for cp in cps.iter() {
-        if !group.contains_cp(*cp) {
-            return Err(ProcessError::Confused(format!(
-                "symbol {} not present in group {}",
-                utils::cp2str(*cp),
-                group.name
-            )));
-        }
-    }
     if group.cm_absent {
         let decomposed = utils::nfd_cps(cps, specs);
         let mut i = 1;
@@ -269,7 +671,9 @@ fn check_whole(
 ) -> Result<(), ProcessError> {
     let (maker, shared) = get_groups_candidates_and_shared_cps(unique_cps, specs);
     for group_name in maker {
-        let confused_group_candidate = specs.group_by_name(group_name).expect("group must exist");
+        let confused_group_candidate = specs
+            .group_by_name(group_name)
+            .expect("whole_map groups are checked when specs are built");
         if confused_group_candidate.contains_all_cps(&shared) {
             return Err(ProcessError::ConfusedGroups {
                 group1: group.name.to_string(),
@@ -280,6 +684,53 @@ fn check_whole(
     Ok(())
 }
 
+/// A label that validated but is one code point away from being whole-script confusable with `group`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusabilityNote {
+    pub label_index: usize,
+    pub group: String,
+    /// The only shared code point of the label missing from `group`
+    pub missing: CodePoint,
+}
+
+/// Runs the whole-script confusable analysis on validated labels and reports near misses:
+/// confusable groups that contain all but one of the label's shared code points
+pub fn confusability_notes(
+    labels: &[ValidatedLabel],
+    specs: &CodePointsSpecs,
+) -> Vec<ConfusabilityNote> {
+    let mut notes = vec![];
+    for (label_index, label) in labels.iter().enumerate() {
+        if label.label_type == LabelType::Emoji {
+            continue;
+        }
+        let unique_cps = label
+            .as_label()
+            .get_cps_of_not_ignored_text()
+            .into_iter()
+            .unique()
+            .collect::<Vec<_>>();
+        let (maker, shared) = get_groups_candidates_and_shared_cps(&unique_cps, specs);
+        for group_name in maker {
+            let group = specs
+                .group_by_name(group_name)
+                .expect("whole_map groups are checked when specs are built");
+            if group.name == label.label_type {
+                continue;
+            }
+            let mut missing = shared.iter().filter(|cp| !group.contains_cp(**cp));
+            if let (Some(cp), None) = (missing.next(), missing.next()) {
+                notes.push(ConfusabilityNote {
+                    label_index,
+                    group: group.name.to_string(),
+                    missing: *cp,
+                });
+            }
+        }
+    }
+    notes
+}
+
 fn get_groups_candidates_and_shared_cps(
     unique_cps: &[CodePoint],
     specs: &CodePointsSpecs,
@@ -316,22 +767,9 @@ fn get_groups_candidates_and_shared_cps(
     (maybe_groups.unwrap_or_default(), shared)
 }
 
-fn determine_group<'a>(
-    unique_cps: &'a [CodePoint],
-    specs: &'a CodePointsSpecs,
-) -> Result<&'a ParsedGroup, ProcessError> {
-    specs
-        .groups_for_cps(unique_cps)
-        .next()
-        .ok_or(ProcessError::Confused(format!(
-            "no group found for {:?}",
-            unique_cps
-        )))
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::TokenizedName;
+    use crate::{Script, TokenEmoji, TokenIgnored, TokenValid, TokenizedName};
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -358,13 +796,13 @@ mod tests {
         maybe_suggest: Some("".to_string())
     }))]
     #[case::fenced_leading("’85", Err(ProcessError::CurrableError {
-        inner: CurrableError::FencedLeading,
+        inner: CurrableError::FencedLeading { name: "apostrophe".to_string() },
         index: 0,
         sequence: "’".to_string(),
         maybe_suggest: Some("".to_string())
     }))]
     #[case::fenced_contiguous("a・・a", Err(ProcessError::CurrableError {
-        inner: CurrableError::FencedConsecutive,
+        inner: CurrableError::FencedConsecutive { name: "middle dot".to_string() },
         index: 1,
         sequence: "・・".to_string(),
         maybe_suggest: Some("・".to_string())
@@ -375,12 +813,40 @@ mod tests {
         sequence: "\u{300}".to_string(),
         maybe_suggest: Some("".to_string())
     }))]
+    #[case::cm_after_emoji_after_mapped("™👍\u{303}", Err(ProcessError::CurrableError {
+        inner: CurrableError::CmAfterEmoji,
+        index: 2,
+        sequence: "\u{303}".to_string(),
+        maybe_suggest: Some("".to_string())
+    }))]
+    #[case::cm_after_emoji_after_ignored("a\u{AD}👍\u{303}", Err(ProcessError::CurrableError {
+        inner: CurrableError::CmAfterEmoji,
+        index: 3,
+        sequence: "\u{303}".to_string(),
+        maybe_suggest: Some("".to_string())
+    }))]
     #[case::cm_leading("\u{300}hello", Err(ProcessError::CurrableError {
         inner: CurrableError::CmStart,
         index: 0,
         sequence: "\u{300}".to_string(),
         maybe_suggest: Some("".to_string())
     }))]
+    // kasra (class 32) before fatha (class 30) is not in canonical order
+    #[case::nsm_distinct_reordered("ب\u{650}\u{64E}", Ok(LabelType::Other("Arabic".to_string())))]
+    #[case::mapped_output_outside_group("аA", Err(ProcessError::Confused(
+        "no group found for [U+0430 (Cyrillic), U+0061 (Basic Latin) mapped from U+0041 (Basic Latin)]".to_string()
+    )))]
+    #[case::unmapped_outside_group("аa", Err(ProcessError::Confused(
+        "no group found for [U+0430 (Cyrillic), U+0061 (Basic Latin)]".to_string()
+    )))]
+    #[case::nsm_repeated_reordered(
+        "ب\u{650}\u{64E}\u{650}",
+        Err(ProcessError::DisallowedSequence(DisallowedSequence::NsmRepeated))
+    )]
+    #[case::nsm_too_many_reordered(
+        "ب\u{652}\u{650}\u{64F}\u{64E}\u{651}",
+        Err(ProcessError::DisallowedSequence(DisallowedSequence::NsmTooMany))
+    )]
     fn test_validate_and_get_type(
         #[case] input: &str,
         #[case] expected: Result<LabelType, ProcessError>,
@@ -388,7 +854,10 @@ mod tests {
     ) {
         let name = TokenizedName::from_input(input, specs, true).unwrap();
         let label = name.iter_labels().next().unwrap();
-        let result = validate_label(label, specs);
+        let result = validate_label(label.clone(), specs);
+        if let Ok(validated) = &result {
+            assert_eq!(validated.as_label(), label);
+        }
         assert_eq!(
             result.clone().map(|v| v.label_type),
             expected,
@@ -397,6 +866,118 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case::cyrillic_and_latin("аa")]
+    #[case::latin_and_greek("aα")]
+    #[case::words("helloпривет")]
+    fn test_resolve_group_error_matches_validation(#[case] input: &str, specs: &CodePointsSpecs) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        let label = name.iter_labels().next().unwrap();
+        let validation_err = validate_label(label, specs).unwrap_err();
+        let resolve_err = specs
+            .resolve_group(&utils::str2cps(input))
+            .map(|group| group.name.to_string())
+            .unwrap_err();
+        assert_eq!(resolve_err, validation_err);
+    }
+
+    #[rstest]
+    #[case::only_stop(".", 2)]
+    #[case::trailing_stop("a.", 2)]
+    #[case::leading_stop(".a", 2)]
+    #[case::two_trailing_stops("a..", 3)]
+    #[case::ignored_label("a.\u{AD}", 2)]
+    fn test_validate_name_empty_labels(
+        #[case] input: &str,
+        #[case] labels_count: usize,
+        specs: &CodePointsSpecs,
+    ) {
+        let name = TokenizedName::from_input(input, specs, true).unwrap();
+        assert_eq!(name.iter_labels().count(), labels_count);
+        assert_eq!(
+            validate_name(&name, specs, &ValidationOptions::default()),
+            Err(ProcessError::DisallowedSequence(
+                DisallowedSequence::EmptyLabel
+            ))
+        );
+    }
+
+    #[rstest]
+    fn test_validate_name_empty_input(specs: &CodePointsSpecs) {
+        let name = TokenizedName::from_input("", specs, true).unwrap();
+        assert_eq!(name.iter_labels().count(), 1);
+        assert_eq!(
+            validate_name(&name, specs, &ValidationOptions::default()),
+            Ok(vec![])
+        );
+    }
+
+    #[rstest]
+    #[case::ascii(vec![EnsNameToken::Valid(TokenValid { cps: vec![0x61] })], false)]
+    #[case::emoji(
+        vec![EnsNameToken::Emoji(TokenEmoji {
+            input: "💩".to_string(),
+            emoji: vec![0x1F4A9, 0xFE0F],
+            cps_input: vec![0x1F4A9],
+            cps_no_fe0f: vec![0x1F4A9],
+        })],
+        false
+    )]
+    #[case::only_ignored(vec![EnsNameToken::Ignored(TokenIgnored { cp: 0xAD })], true)]
+    #[case::only_marks(vec![EnsNameToken::Valid(TokenValid { cps: vec![0x301, 0x308] })], true)]
+    #[case::base_with_mark(vec![EnsNameToken::Valid(TokenValid { cps: vec![0x61, 0x301] })], false)]
+    fn test_is_degenerate(
+        #[case] tokens: Vec<EnsNameToken>,
+        #[case] expected: bool,
+        specs: &CodePointsSpecs,
+    ) {
+        let label = ValidatedLabel {
+            tokens,
+            label_type: LabelType::Ascii,
+            restricted: false,
+        };
+        assert_eq!(label.is_degenerate(specs), expected);
+    }
+
+    #[rstest]
+    #[case::cyrillic("привет", Ok(()))]
+    #[case::mapped("Привет", Ok(()))]
+    #[case::latin("hello", Err(ProcessError::Confused(
+        "U+0068 (Basic Latin) is not in group Cyrillic".to_string()
+    )))]
+    #[case::two_labels("при.вет", Err(ProcessError::DisallowedSequence(
+        DisallowedSequence::Invalid {
+            sequence: ".".to_string(),
+            index: 3
+        }
+    )))]
+    #[case::empty(
+        "",
+        Err(ProcessError::DisallowedSequence(DisallowedSequence::EmptyLabel))
+    )]
+    fn test_label_validator(
+        #[case] input: &str,
+        #[case] expected: Result<(), ProcessError>,
+        specs: &CodePointsSpecs,
+    ) {
+        let validator = specs
+            .validator_for_group("Cyrillic".to_string())
+            .expect("group exists");
+        let result = validator.validate(input);
+        if let Ok(label) = &result {
+            assert_eq!(label.label_type, LabelType::Other("Cyrillic".to_string()));
+            let name = TokenizedName::from_input(input, specs, true).unwrap();
+            let expected = validate_label(name.iter_labels().next().unwrap(), specs).unwrap();
+            assert_eq!(label, &expected.into_owned());
+        }
+        assert_eq!(result.map(|_| ()), expected);
+    }
+
+    #[rstest]
+    fn test_validator_for_unknown_group(specs: &CodePointsSpecs) {
+        assert!(specs.validator_for_group("Klingon".to_string()).is_none());
+    }
+
     #[rstest]
     #[case::emoji("\"Emoji\"", LabelType::Emoji)]
     #[case::ascii("\"ASCII\"", LabelType::Ascii)]
@@ -406,4 +987,18 @@ mod tests {
         let result: LabelType = serde_json::from_str(input).unwrap();
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    #[case::latin("Latin", Some(Script::Latin))]
+    #[case::greek("Greek", Some(Script::Greek))]
+    #[case::cyrillic("Cyrillic", Some(Script::Cyrillic))]
+    #[case::han("Han", Some(Script::Han))]
+    #[case::arabic("Arabic", Some(Script::Arabic))]
+    #[case::hebrew("Hebrew", Some(Script::Hebrew))]
+    #[case::ascii("ASCII", None)]
+    #[case::emoji("Emoji", None)]
+    #[case::unknown("Tfng", None)]
+    fn test_label_type_script(#[case] name: &str, #[case] expected: Option<Script>) {
+        assert_eq!(LabelType::from(name.to_string()).script(), expected);
+    }
 }
@@ -0,0 +1,50 @@
+//! `wasm-bindgen` bindings, enabled with the `wasm` feature.
+//! All functions share a single lazily created [`EnsNameNormalizer`] with the bundled specs.
+
+use crate::EnsNameNormalizer;
+use lazy_static::lazy_static;
+use wasm_bindgen::prelude::*;
+
+lazy_static! {
+    static ref NORMALIZER: EnsNameNormalizer = EnsNameNormalizer::default();
+}
+
+/// Normalize the ENS name, throws the error message if the name is invalid
+#[wasm_bindgen]
+pub fn normalize(input: &str) -> Result<String, JsError> {
+    NORMALIZER.normalize(input).map_err(to_js_error)
+}
+
+/// Beautify the ENS name, throws the error message if the name is invalid
+#[wasm_bindgen]
+pub fn beautify(input: &str) -> Result<String, JsError> {
+    NORMALIZER.beautify(input).map_err(to_js_error)
+}
+
+/// Tokenize the ENS name, returns JSON array of tokens
+#[wasm_bindgen]
+pub fn tokenize(input: &str) -> Result<String, JsError> {
+    let tokenized = NORMALIZER.tokenize(input).map_err(to_js_error)?;
+    serde_json::to_string(&tokenized.tokens).map_err(to_js_error)
+}
+
+/// Process the ENS name, returns JSON report with normalized and beautified
+/// forms and label types, throws the error message if the name is invalid
+#[wasm_bindgen]
+pub fn process(input: &str) -> Result<String, JsError> {
+    let processed = NORMALIZER.process(input).map_err(to_js_error)?;
+    let report = serde_json::json!({
+        "normalized": processed.normalize(),
+        "beautified": processed.beautify(),
+        "labels": processed
+            .labels
+            .iter()
+            .map(|label| label.label_type.to_string())
+            .collect::<Vec<_>>(),
+    });
+    Ok(report.to_string())
+}
+
+fn to_js_error(err: impl std::fmt::Display) -> JsError {
+    JsError::new(&err.to_string())
+}
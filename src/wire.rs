@@ -0,0 +1,140 @@
+use crate::{
+    utils, CodePoint, CollapsedEnsNameToken, EnsNameToken, LabelType, ProcessedName, TokenEmoji,
+    TokenValid, TokenizedName, ValidatedLabel,
+};
+use anyhow::{bail, Context};
+
+const VERSION: u8 = 1;
+const TAG_TEXT: u8 = 0;
+const TAG_EMOJI: u8 = 1;
+
+/// Encodes labels of a processed name: per label its type, restriction flag and
+/// collapsed text and emoji runs, code points being LEB128 varints.
+/// Ignored and disallowed characters of the input are not kept.
+pub(crate) fn encode(name: &ProcessedName) -> Vec<u8> {
+    let mut bytes = vec![VERSION];
+    write_varint(&mut bytes, name.substitutions as u64);
+    write_varint(&mut bytes, name.labels.len() as u64);
+    for label in &name.labels {
+        let label_type = label.label_type.to_string();
+        write_varint(&mut bytes, label_type.len() as u64);
+        bytes.extend_from_slice(label_type.as_bytes());
+        bytes.push(label.restricted as u8);
+        let runs = label.as_label().collapse_into_text_or_emoji();
+        write_varint(&mut bytes, runs.len() as u64);
+        for run in runs {
+            let (tag, cps) = match &run {
+                CollapsedEnsNameToken::Text(text) => (TAG_TEXT, &text.cps),
+                CollapsedEnsNameToken::Emoji(emoji) => (TAG_EMOJI, &emoji.emoji),
+            };
+            bytes.push(tag);
+            write_varint(&mut bytes, cps.len() as u64);
+            for cp in cps {
+                write_varint(&mut bytes, *cp as u64);
+            }
+        }
+    }
+    bytes
+}
+
+/// Decodes bytes produced by [`encode`]. The tokenized name is rebuilt from the labels,
+/// so its `input` is the normalized name and `spans` are empty.
+pub(crate) fn decode(bytes: &[u8]) -> anyhow::Result<ProcessedName> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let version = reader.byte()?;
+    if version != VERSION {
+        bail!("unsupported version {version}");
+    }
+    let substitutions = reader.varint()? as usize;
+    let label_count = reader.varint()?;
+    let mut labels = Vec::new();
+    for _ in 0..label_count {
+        let len = reader.varint()? as usize;
+        let label_type = std::str::from_utf8(reader.take(len)?).context("invalid label type")?;
+        let restricted = reader.byte()? != 0;
+        let run_count = reader.varint()?;
+        let mut tokens = Vec::new();
+        for _ in 0..run_count {
+            let tag = reader.byte()?;
+            let len = reader.varint()?;
+            let cps = (0..len)
+                .map(|_| reader.code_point())
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            tokens.push(match tag {
+                TAG_TEXT => EnsNameToken::Valid(TokenValid { cps }),
+                TAG_EMOJI => EnsNameToken::Emoji(TokenEmoji {
+                    input: utils::cps2str(&cps),
+                    cps_no_fe0f: utils::filter_fe0f(&cps),
+                    cps_input: cps.clone(),
+                    emoji: cps,
+                }),
+                tag => bail!("unknown token tag {tag}"),
+            });
+        }
+        labels.push(ValidatedLabel {
+            tokens,
+            label_type: LabelType::from(label_type.to_string()),
+            restricted,
+        });
+    }
+    if reader.pos != bytes.len() {
+        bail!("trailing bytes after {} labels", labels.len());
+    }
+    let tokens = itertools::intersperse(
+        labels.iter().map(|label| label.tokens.clone()),
+        vec![EnsNameToken::stop()],
+    )
+    .flatten();
+    let tokenized = TokenizedName::from_tokens(crate::join_labels(&labels), tokens);
+    Ok(ProcessedName {
+        labels,
+        tokenized,
+        substitutions,
+    })
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).context("length overflow")?;
+        let slice = self.bytes.get(self.pos..end).context("unexpected end")?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> anyhow::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("varint is too long")
+    }
+
+    fn code_point(&mut self) -> anyhow::Result<CodePoint> {
+        let value = self.varint()?;
+        match CodePoint::try_from(value).ok().and_then(char::from_u32) {
+            Some(c) => Ok(c as CodePoint),
+            None => bail!("invalid code point {value:#x}"),
+        }
+    }
+}
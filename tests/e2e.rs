@@ -1,6 +1,15 @@
-use ens_normalize_rs::{CurrableError, DisallowedSequence, EnsNameNormalizer, ProcessError};
+use ens_normalize_rs::{
+    beautify_labels, join_labels, CollisionReason, ConfusabilityNote, CurrableError, DiffOp,
+    DisallowedPolicy, DisallowedSequence, EmojiPolicy, EnsNameNormalizer, LabelType, ProcessError,
+    ProcessedName, TokenizedName, Transform, TransformKind, Warning,
+};
 use pretty_assertions::assert_eq;
 use rstest::{fixture, rstest};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+};
 
 #[fixture]
 #[once]
@@ -33,17 +42,28 @@ fn normalizer() -> EnsNameNormalizer {
 )]
 #[case(
     "・abcd",
-    Err(currable_error(CurrableError::FencedLeading, 0, "・", Some("")))
+    Err(currable_error(fenced_leading("middle dot"), 0, "・", Some("")))
 )]
 #[case(
     "abcd・",
-    Err(currable_error(CurrableError::FencedTrailing, 4, "・", Some("")))
+    Err(currable_error(fenced_trailing("middle dot"), 4, "・", Some("")))
 )]
 #[case(
     "a・’a",
-    Err(currable_error(CurrableError::FencedConsecutive, 1, "・’", Some("・")))
+    Err(currable_error(fenced_consecutive("middle dot"), 1, "・’", Some("・")))
 )]
-#[case("vitalik .eth", Err(disallowed(" ")))]
+#[case("vitalik .eth", Err(disallowed(" ", 7)))]
+#[case("💩\u{200D}💩", Err(unknown_emoji("💩\u{200D}💩")))]
+#[case("a!\u{FE0F}", Err(unknown_emoji("!\u{FE0F}")))]
+#[case("💩!💩", Err(disallowed("!", 1)))]
+#[case("a@@@b.eth", Err(disallowed("@@@", 1)))]
+#[case("a@b@c.eth", Err(disallowed("@", 1)))]
+#[case("a@\u{AD}@b.eth", Err(disallowed("@", 1)))]
+#[case("Ni\u{200D}ck.eth", Err(invisible(0x200D)))]
+#[case("Ni\u{200C}ck.eth", Err(invisible(0x200C)))]
+#[case("\u{200D}nick.eth", Err(invisible(0x200D)))]
+#[case("nick\u{200D}.eth", Err(invisible(0x200D)))]
+#[case("💩a\u{200D}b", Err(invisible(0x200D)))]
 #[case("vitalik..eth", Err(empty_label()))]
 #[case("..", Err(empty_label()))]
 fn e2e_tests(
@@ -70,14 +90,1387 @@ fn e2e_tests(
     }
 }
 
-fn disallowed(sequence: &str) -> ProcessError {
-    ProcessError::DisallowedSequence(DisallowedSequence::Invalid(sequence.to_string()))
+#[rstest]
+#[case("ab.eth", Ok("ab.eth"))]
+#[case("a.eth", Err(label_too_short(0, 1)))]
+#[case("👨‍💻.eth", Ok("👨‍💻.eth"))]
+#[case("ab.e", Err(label_too_short(1, 1)))]
+fn min_label_length_tests(#[case] name: &str, #[case] expected: Result<&str, ProcessError>) {
+    let normalizer = EnsNameNormalizer::default().min_label_length(2);
+    let actual = normalizer.normalize(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case("vitalik.eth", Ok("vitalik.eth"))]
+#[case(
+    "vi_talik.eth",
+    Err(currable_error(CurrableError::UnderscoreInMiddle, 2, "_", Some("")))
+)]
+#[case(
+    "__vitalik.eth",
+    Err(currable_error(CurrableError::LeadingUnderscoreForbidden, 0, "__", Some("")))
+)]
+#[case(
+    "vitalik._eth",
+    Err(currable_error(CurrableError::LeadingUnderscoreForbidden, 0, "_", Some("")))
+)]
+fn forbid_leading_underscore_tests(
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().forbid_leading_underscore(true);
+    let actual = normalizer.normalize(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::all_zwj(EmojiPolicy::All, "👨‍💻.eth", Ok("👨‍💻.eth"))]
+#[case::no_zwj_single(EmojiPolicy::NoZwj, "💩.eth", Ok("💩.eth"))]
+#[case::no_zwj_sequence(EmojiPolicy::NoZwj, "dev👨‍💻.eth", Err(emoji_not_allowed(&[0x1F468, 0x200D, 0x1F4BB])))]
+#[case::none_ascii(EmojiPolicy::None, "vitalik.eth", Ok("vitalik.eth"))]
+#[case::none_single(EmojiPolicy::None, "vitalik.💩", Err(emoji_not_allowed(&[0x1F4A9])))]
+fn emoji_policy_tests(
+    #[case] policy: EmojiPolicy,
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().emoji_policy(policy);
+    let actual = normalizer.normalize(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::error(DisallowedPolicy::Error, "vi!talik.eth", Err(disallowed("!", 2)))]
+#[case::remove(DisallowedPolicy::Remove, "Vi!talik.eth", Ok(("vitalik.eth", 1)))]
+#[case::replace(DisallowedPolicy::Replace('\u{FFFD}'), "vi!tal!k.eth", Ok(("vi\u{FFFD}tal\u{FFFD}k.eth", 2)))]
+#[case::replace_valid(DisallowedPolicy::Replace('\u{FFFD}'), "vitalik.eth", Ok(("vitalik.eth", 0)))]
+#[case::remove_whole_label(DisallowedPolicy::Remove, "!.eth", Err(empty_label()))]
+#[case::still_validated(
+    DisallowedPolicy::Remove,
+    "a!_.eth",
+    Err(currable_error(CurrableError::UnderscoreInMiddle, 2, "_", Some("")))
+)]
+#[case::control_character_removed(
+    DisallowedPolicy::Remove,
+    "vitalik\0.eth",
+    Err(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0)))
+)]
+#[case::control_character_replaced(
+    DisallowedPolicy::Replace('\u{FFFD}'),
+    "vitalik\n.eth",
+    Err(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0x0A)))
+)]
+fn on_disallowed_tests(
+    #[case] policy: DisallowedPolicy,
+    #[case] name: &str,
+    #[case] expected: Result<(&str, usize), ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(policy);
+    let actual = normalizer
+        .process(name)
+        .map(|processed| (processed.normalize(), processed.substitutions()));
+    assert_eq!(actual, expected.map(|(s, n)| (s.to_string(), n)));
+}
+
+#[rstest]
+#[case::precomposed("caf\u{E9}.eth", false, Ok("caf\u{E9}.eth"))]
+#[case::decomposed_allowed("cafe\u{301}.eth", false, Ok("caf\u{E9}.eth"))]
+#[case::decomposed("cafe\u{301}.eth", true, Err(not_nfc(3)))]
+#[case::after_mapped("CAFE\u{301}.eth", true, Err(not_nfc(3)))]
+#[case::second_label("eth.cafe\u{301}", true, Err(not_nfc(7)))]
+fn require_nfc_tests(
+    #[case] name: &str,
+    #[case] require: bool,
+    #[case] expected: Result<&str, ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().require_nfc(require);
+    let actual = normalizer.process(name).map(|p| p.normalize());
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::within_limit("sub.vitalik.eth", 3, Ok(3))]
+#[case::empty("", 1, Ok(0))]
+#[case::too_many("a.sub.vitalik.eth", 3, Err(too_many_labels(4, 3)))]
+fn max_labels_tests(
+    #[case] name: &str,
+    #[case] max: usize,
+    #[case] expected: Result<usize, ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().max_labels(max);
+    let actual = normalizer
+        .process(name)
+        .map(|processed| processed.label_count());
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::valid("Vitalik.💩", Ok(vec![LabelType::Ascii, LabelType::Emoji]))]
+#[case::empty("", Ok(vec![]))]
+#[case::empty_label("vitalik..eth", Err(empty_label()))]
+fn process_ref_tests(
+    #[case] name: &str,
+    #[case] expected: Result<Vec<LabelType>, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let tokenized = normalizer.tokenize(name).expect("tokenize");
+    let labels = normalizer.process_ref(&tokenized);
+    let actual = labels
+        .clone()
+        .map(|labels| labels.into_iter().map(|l| l.label_type).collect::<Vec<_>>());
+    assert_eq!(actual, expected);
+    if let Ok(labels) = labels {
+        let owned = labels
+            .into_iter()
+            .map(|label| label.into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(owned, normalizer.process(name).unwrap().labels);
+    }
+}
+
+#[test]
+fn process_ref_ignores_disallowed_policy() {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(DisallowedPolicy::Remove);
+    let tokenized = normalizer.tokenize("a!b.eth").expect("tokenize");
+    assert!(normalizer.process("a!b.eth").is_ok());
+    assert_eq!(
+        normalizer.process_ref(&tokenized).map(|_| ()),
+        Err(disallowed("!", 1))
+    );
+}
+
+#[rstest]
+fn spans_and_substitutions_accessors(normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process("Ｖi\u{AD}.eth").unwrap();
+    assert_eq!(processed.tokenized.spans(), [0..3, 3..4, 4..6, 6..7, 7..10]);
+    assert_eq!(processed.substitutions(), 0);
+    let tokenized = TokenizedName::from_tokens("vi", processed.tokenized.tokens.clone());
+    assert!(tokenized.spans().is_empty());
+}
+
+#[test]
+fn normalizer_is_clone() {
+    fn assert_clone<T: Clone>() {}
+    assert_clone::<EnsNameNormalizer>();
+}
+
+#[rstest]
+#[case::valid("Vitalik.eth")]
+#[case::emoji("💩\u{FE0F}.eth")]
+#[case::invalid("vitalik .eth")]
+fn cloned_normalizer_tests(#[case] name: &str) {
+    let normalizer = EnsNameNormalizer::default()
+        .min_label_length(3)
+        .on_disallowed(DisallowedPolicy::Remove);
+    let cloned = normalizer.clone();
+    assert!(std::ptr::eq(normalizer.specs(), cloned.specs()));
+    assert_eq!(
+        cloned.process(name).map(|p| p.beautify()),
+        normalizer.process(name).map(|p| p.beautify())
+    );
+}
+
+#[rstest]
+#[case::same_input("vitalik.eth", "vitalik.eth", true)]
+#[case::mapped("Vitalik.eth", "vitalik.eth", true)]
+#[case::ignored("vita\u{AD}lik.eth", "vitalik.eth", true)]
+#[case::emoji_fe0f("❤️.eth", "❤.eth", true)]
+#[case::different("vitalik.eth", "nick.eth", false)]
+#[case::different_labels("vitalik.eth", "vitalik.eth.eth", false)]
+fn processed_name_eq_tests(
+    #[case] a: &str,
+    #[case] b: &str,
+    #[case] expected: bool,
+    normalizer: &EnsNameNormalizer,
+) {
+    let a = normalizer.process(a).expect("name should be valid");
+    let b = normalizer.process(b).expect("name should be valid");
+    assert_eq!(a == b, expected);
+    assert_eq!(
+        a.tokenized == b.tokenized,
+        a.tokenized.input == b.tokenized.input
+    );
+}
+
+#[rstest]
+#[case::emoji("💩👍🏻", Ok("💩👍🏻"))]
+#[case::emoji_fe0f("❤️", Ok("❤"))]
+#[case::mixed("💩a", Err(not_fully_emoji()))]
+#[case::several_labels("💩.eth", Err(not_fully_emoji()))]
+#[case::empty("", Err(not_fully_emoji()))]
+#[case::invalid("💩..", Err(empty_label()))]
+fn normalize_emoji_label_tests(
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.normalize_emoji_label(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::plain("vitalik.eth", 0, Some("vitalik"))]
+#[case::mapped_and_ignored("\u{FF36}i\u{AD}talik.eth", 0, Some("\u{FF36}i\u{AD}talik"))]
+#[case::last("vitalik.ETH", 1, Some("ETH"))]
+#[case::emoji("\u{1F4A9}\u{FE0F}.eth", 0, Some("\u{1F4A9}\u{FE0F}"))]
+#[case::out_of_range("vitalik.eth", 2, None)]
+fn input_label_tests(
+    #[case] name: &str,
+    #[case] index: usize,
+    #[case] expected: Option<&str>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(name).expect("name should be valid");
+    assert_eq!(processed.input_label(index), expected);
+}
+
+#[rstest]
+#[case::prefix("Vita", Ok("vita"))]
+#[case::trailing_stop("raffy.", Ok("raffy."))]
+#[case::partial_tld("Raffy.E", Ok("raffy.e"))]
+#[case::empty_labels("..a", Ok("..a"))]
+#[case::leading_cm("\u{303}a", Ok("\u{303}a"))]
+#[case::mixed_scripts("aа", Ok("aа"))]
+#[case::nfc("e\u{301}", Ok("é"))]
+#[case::emoji("\u{1F4A9}\u{FE0F}", Ok("\u{1F4A9}"))]
+#[case::ignored("a\u{AD}b", Ok("ab"))]
+#[case::empty("", Ok(""))]
+#[case::disallowed("vita!", Err(disallowed("!", 4)))]
+#[case::disallowed_in_second_label("ab.c!", Err(disallowed("!", 1)))]
+#[case::control_character(
+    "a\0",
+    Err(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0)))
+)]
+fn normalize_fragment_tests(
+    #[case] input: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.normalize_fragment(input);
+    if let Err(err) = &actual {
+        assert_eq!(normalizer.normalize(input).as_ref(), Err(err));
+    }
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+fn ens_emoji_beautified(normalizer: &EnsNameNormalizer) {
+    let emoji = ens_normalize_rs::ens_emoji();
+    assert!(emoji.contains(&"\u{2764}\u{FE0F}".to_string()));
+    for emoji in emoji.iter().step_by(50) {
+        assert_eq!(normalizer.beautify(emoji).as_ref(), Ok(emoji));
+    }
+}
+
+#[rstest]
+fn spec_versions(normalizer: &EnsNameNormalizer) {
+    assert_eq!(normalizer.unicode_version(), "16.0.0");
+    assert_eq!(normalizer.cldr_version(), "45");
+    assert_eq!(normalizer.created(), normalizer.specs().created());
+}
+
+#[test]
+fn normalize_fragment_removes_disallowed() {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(DisallowedPolicy::Remove);
+    assert_eq!(
+        normalizer.normalize_fragment("vi!ta."),
+        Ok("vita.".to_string())
+    );
+}
+
+#[rstest]
+#[case::valid("Vitalik.eth", vec![("Vitalik", 0, Ok("vitalik")), ("eth", 8, Ok("eth"))])]
+#[case::bad_middle(
+    "good.BAD!.go\u{AD}od",
+    vec![("good", 0, Ok("good")), ("BAD!", 5, Err(disallowed("!", 3))), ("go\u{AD}od", 10, Ok("good"))],
+)]
+#[case::empty_label("a..b", vec![("a", 0, Ok("a")), ("", 2, Err(empty_label())), ("b", 3, Ok("b"))])]
+#[case::emoji_offset("\u{1F4A9}\u{FE0F}.eth", vec![("\u{1F4A9}\u{FE0F}", 0, Ok("\u{1F4A9}")), ("eth", 3, Ok("eth"))])]
+#[case::empty("", vec![])]
+#[case::control_character("a.\0", vec![("a.\0", 0, Err(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0))))])]
+fn split_tests(
+    #[case] name: &str,
+    #[case] expected: Vec<(&str, usize, Result<&str, ProcessError>)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer
+        .split(name)
+        .into_iter()
+        .map(|label| {
+            let normalized = label
+                .result
+                .map(|validated| ens_normalize_rs::join_labels(&[validated]));
+            (label.input, label.offset, normalized)
+        })
+        .collect::<Vec<_>>();
+    let expected = expected
+        .into_iter()
+        .map(|(input, offset, result)| (input.to_string(), offset, result.map(String::from)))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::remove(DisallowedPolicy::Remove, "bad")]
+#[case::replace(DisallowedPolicy::Replace('_'), "bad_")]
+fn split_disallowed_policy_tests(#[case] policy: DisallowedPolicy, #[case] expected: &str) {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(policy);
+    let labels = normalizer.split("good.BAD!");
+    assert_eq!(labels.len(), 2);
+    let validated = labels[1].result.clone().expect("label should be valid");
+    assert_eq!(ens_normalize_rs::join_labels(&[validated]), expected);
+}
+
+#[rstest]
+#[case::ascii("vitalik.eth")]
+#[case::mixed_script_emoji("Vitalik❤️\u{200D}🔥.ωμέγα.привет💩.ETH")]
+#[case::xi("-ξ1⃣.eth")]
+#[case::empty("")]
+fn bytes_round_trip_tests(#[case] name: &str, normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process(name).expect("name should be valid");
+    let decoded = ProcessedName::from_bytes(&processed.to_bytes()).expect("bytes should decode");
+    assert_eq!(decoded.normalize(), processed.normalize());
+    assert_eq!(decoded.beautify(), processed.beautify());
+    assert_eq!(decoded.label_report(), processed.label_report());
+    assert_eq!(decoded.tokenized.input, processed.normalize());
+}
+
+#[rstest]
+#[case::empty(&[])]
+#[case::unknown_version(&[2, 0, 0])]
+#[case::truncated(&[1, 0, 1, 5, b'L'])]
+#[case::trailing(&[1, 0, 0, 0])]
+#[case::surrogate(&[1, 0, 1, 5, b'L', b'a', b't', b'i', b'n', 0, 1, 0, 1, 0x80, 0xB0, 0x03])]
+fn bytes_invalid_tests(#[case] bytes: &[u8]) {
+    assert!(ProcessedName::from_bytes(bytes).is_err());
+}
+
+#[rstest]
+#[case::valid("Vitalik.eth", Ok("vitalik.eth"))]
+#[case::every_label("XN--vitalik.e_th", Ok("xnvitalik.eth"))]
+#[case::fenced("’vitalik’.eth", Ok("vitalik.eth"))]
+#[case::leading_cm("\u{303}vitalik.eth", Ok("vitalik.eth"))]
+#[case::empty("", Ok(""))]
+#[case::empty_label("vitalik..eth", Err(empty_label()))]
+#[case::cured_to_empty("’.eth", Err(empty_label()))]
+#[case::disallowed("vi_talik!.eth", Err(disallowed("!", 8)))]
+fn cure_tests(
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.cure(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[test]
+fn cure_checks_name_wide_options() {
+    let normalizer = EnsNameNormalizer::default().max_labels(1);
+    assert_eq!(normalizer.cure("a_b.eth"), Err(too_many_labels(2, 1)));
+}
+
+#[rstest]
+#[case::valid("Vitalik", Ok("vitalik"))]
+#[case::underscores_in_middle("vi_ta_lik", Ok("vitalik"))]
+#[case::leading_underscore_kept("__vitalik", Ok("__vitalik"))]
+#[case::hyphens("XN--vitalik", Ok("xnvitalik"))]
+#[case::fenced_leading_trailing("’vitalik’", Ok("vitalik"))]
+#[case::fenced_consecutive("a・’a", Ok("a・a"))]
+#[case::leading_cm("\u{303}vitalik", Ok("vitalik"))]
+#[case::cm_after_emoji("💩\u{303}vitalik", Ok("💩vitalik"))]
+#[case::mapped_then_cured("ＶＩ＿ＴＡＬＩＫ", Ok("vitalik"))]
+#[case::several_fixes("’_a_b’", Ok("ab"))]
+#[case::many_fixes("a_b_c_d_e_f_g", Ok("abcdefg"))]
+#[case::expanded_by_mapping("\u{216B}_\u{216B}_\u{216B}", Ok("xiixiixii"))]
+#[case::cured_to_empty("’", Err(empty_label()))]
+#[case::disallowed("vitalik!", Err(disallowed("!", 7)))]
+#[case::several_labels("vitalik.eth", Err(disallowed(".", 7)))]
+#[case::empty("", Err(empty_label()))]
+fn cure_label_tests(
+    #[case] label: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.cure_label(label);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::private_use_as_letter("\u{E000}bc.eth", Ok("abc.eth"))]
+#[case::several_code_points("x\u{E001}.eth", Ok("xyz.eth"))]
+#[case::premapped_then_mapped("\u{E002}bc.eth", Ok("abc.eth"))]
+#[case::untouched("vitalik.eth", Ok("vitalik.eth"))]
+#[case::other_private_use("\u{E003}.eth", Err(disallowed("\u{E003}", 0)))]
+fn with_premap_tests(#[case] name: &str, #[case] expected: Result<&str, ProcessError>) {
+    let premap = HashMap::from([
+        (0xE000, vec!['a' as u32]),
+        (0xE001, vec!['y' as u32, 'z' as u32]),
+        (0xE002, vec!['A' as u32]),
+    ]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    let actual = normalizer.normalize(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+    if let Ok(normalized) = &actual {
+        let (_, changed) = normalizer.normalize_checked(name).unwrap();
+        assert_eq!(changed, normalized != name);
+        assert_eq!(normalizer.is_normalized(name), normalized == name);
+    }
+}
+
+#[test]
+fn with_premap_positions_refer_to_premapped_input() {
+    // 3-byte private use code point premapped to a 1-byte letter
+    let premap = HashMap::from([(0xE000, vec!['a' as u32])]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    let processed = normalizer.process("\u{E000}\u{E000}b.\u{E000}th").unwrap();
+    assert_eq!(processed.tokenized.input, "aab.ath");
+    assert_eq!(processed.input_label(1), Some("ath"));
+    let source = processed
+        .source_map()
+        .into_iter()
+        .map(|(_, range)| range.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(source[..3], [0..1, 1..2, 2..3]);
+    let split = normalizer
+        .split("\u{E000}b.\u{E000}th")
+        .into_iter()
+        .map(|label| (label.input, label.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(split, vec![("ab".to_string(), 0), ("ath".to_string(), 3)]);
+}
+
+#[rstest]
+#[case::control_premapped_to_letter("\u{7}b.eth", vec![("ab", 0, Ok("ab")), ("eth", 3, Ok("eth"))])]
+#[case::premapped_to_control(
+    "a\u{E000}.eth",
+    vec![("a\n.eth", 0, Err(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0xA))))],
+)]
+fn with_premap_split_checks_premapped_input(
+    #[case] name: &str,
+    #[case] expected: Vec<(&str, usize, Result<&str, ProcessError>)>,
+) {
+    let premap = HashMap::from([(0x7, vec!['a' as u32]), (0xE000, vec!['\n' as u32])]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    let actual = normalizer
+        .split(name)
+        .into_iter()
+        .map(|label| {
+            (
+                label.input,
+                label.offset,
+                label.result.map(|l| join_labels(&[l])),
+            )
+        })
+        .collect::<Vec<_>>();
+    let expected = expected
+        .into_iter()
+        .map(|(input, offset, result)| (input.to_string(), offset, result.map(String::from)))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+    // same verdict as processing the whole name
+    let processed = normalizer
+        .process(name)
+        .map(|processed| processed.normalize());
+    match &actual[..] {
+        [(_, _, Err(err))] => assert_eq!(processed.as_ref(), Err(err)),
+        _ => assert!(processed.is_ok()),
+    }
+}
+
+#[test]
+fn with_premap_process_partial_checks_premapped_input() {
+    let premap = HashMap::from([(0x7, vec!['a' as u32])]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    let (labels, err) = normalizer.process_partial("\u{7}b.eth");
+    assert_eq!(err, None);
+    assert_eq!(labels.len(), 2);
+}
+
+#[test]
+fn with_premap_normalize_fragment_checks_premapped_input() {
+    let premap = HashMap::from([(0x7, vec!['a' as u32])]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    assert_eq!(
+        normalizer.normalize_fragment("\u{7}b."),
+        Ok("ab.".to_string())
+    );
+}
+
+#[rstest]
+#[case::nothing_to_cure("ax", "bx")]
+#[case::underscore("a_x", "bx")]
+#[case::several_labels("a_x.a", "bx.b")]
+fn with_premap_cure_premaps_once(#[case] name: &str, #[case] expected: &str) {
+    // chained replacements tell a single premap from a repeated one
+    let premap = HashMap::from([
+        ('a' as u32, vec!['b' as u32]),
+        ('b' as u32, vec!['c' as u32]),
+    ]);
+    let normalizer = EnsNameNormalizer::default().with_premap(premap);
+    assert_eq!(normalizer.cure(name), Ok(expected.to_string()));
+    if !name.contains('.') {
+        assert_eq!(normalizer.cure_label(name), Ok(expected.to_string()));
+    }
+}
+
+#[rstest]
+fn normalize_with_report_tests(normalizer: &EnsNameNormalizer) {
+    let report = normalizer
+        .normalize_with_report("Ａb\u{AD}cafe\u{301}💩\u{FE0F}.eth")
+        .expect("name should be valid");
+    assert_eq!(report.normalized, "abcafé💩.eth");
+    assert_eq!(report.beautified, "abcafé💩\u{FE0F}.eth");
+    assert_eq!(
+        report.label_types,
+        vec![LabelType::Other("Latin".to_string()), LabelType::Ascii]
+    );
+    let transform = |index, kind, from: &str, to: &str| Transform {
+        index,
+        kind,
+        from: from.to_string(),
+        to: to.to_string(),
+    };
+    assert_eq!(
+        report.transforms,
+        vec![
+            transform(0, TransformKind::Mapped, "Ａ", "a"),
+            transform(2, TransformKind::Ignored, "\u{AD}", ""),
+            transform(6, TransformKind::Nfc, "e\u{301}", "é"),
+            transform(8, TransformKind::Emoji, "💩\u{FE0F}", "💩"),
+        ]
+    );
+    assert_eq!(report.warnings, vec![]);
+
+    let report = normalizer.normalize_with_report("ܐܒ.eth").unwrap();
+    assert_eq!(report.transforms, vec![]);
+    assert_eq!(
+        report.warnings,
+        vec![Warning::Restricted {
+            label_index: 0,
+            label_type: LabelType::Other("Syrc".to_string()),
+        }]
+    );
+
+    let report = EnsNameNormalizer::default()
+        .on_disallowed(DisallowedPolicy::Remove)
+        .normalize_with_report("a!b")
+        .unwrap();
+    assert_eq!(report.normalized, "ab");
+    assert_eq!(
+        report.transforms,
+        vec![transform(1, TransformKind::Removed, "!", "")]
+    );
+    assert_eq!(report.warnings, vec![Warning::Substituted { count: 1 }]);
+
+    let report = EnsNameNormalizer::default()
+        .on_disallowed(DisallowedPolicy::Replace('\u{FFFD}'))
+        .normalize_with_report("A!b")
+        .unwrap();
+    assert_eq!(
+        report.transforms,
+        vec![
+            transform(0, TransformKind::Mapped, "A", "a"),
+            transform(1, TransformKind::Replaced, "!", "\u{FFFD}"),
+        ]
+    );
+}
+
+#[rstest]
+#[case::single_label("abc", "abc", 0)]
+#[case::mapped("A.B.eth", "a.b.eth", 2)]
+#[case::removed_disallowed("a!b.eth", "ab.eth", 1)]
+fn flat_tokens_tests(#[case] name: &str, #[case] expected: &str, #[case] stops: usize) {
+    let processed = EnsNameNormalizer::default()
+        .on_disallowed(DisallowedPolicy::Remove)
+        .process(name)
+        .expect("name should be valid");
+    let tokens = processed.flat_tokens();
+    assert!(!tokens.iter().any(|token| token.is_disallowed()));
+    let joined = tokens
+        .iter()
+        .filter(|token| !token.is_ignored())
+        .flat_map(|token| token.cps())
+        .filter_map(char::from_u32)
+        .collect::<String>();
+    assert_eq!(joined, expected);
+    assert_eq!(tokens.iter().filter(|token| token.is_stop()).count(), stops);
+}
+
+#[rstest]
+#[case::text_default_emoji("❤.eth", "❤", "❤\u{FE0F}")]
+#[case::emoji_with_fe0f("❤\u{FE0F}.eth", "❤", "❤\u{FE0F}")]
+#[case::emoji_presentation("💩.eth", "💩", "💩\u{FE0F}")]
+#[case::greek_xi_kept("ξ.eth", "ξ", "ξ")]
+fn normalize_qualified_tests(
+    #[case] name: &str,
+    #[case] normalized_label: &str,
+    #[case] qualified_label: &str,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(name).expect("name should be valid");
+    assert_eq!(processed.normalize(), format!("{normalized_label}.eth"));
+    assert_eq!(
+        processed.normalize_qualified(),
+        format!("{qualified_label}.eth")
+    );
+}
+
+#[rstest]
+#[case::valid("Vitalik.eth", vec!["vitalik", "eth"], None)]
+#[case::second_label_invalid("vitalik.a_b.eth", vec!["vitalik"], Some(currable_error(CurrableError::UnderscoreInMiddle, 1, "_", Some(""))))]
+#[case::first_label_empty(".eth", vec![], Some(empty_label()))]
+#[case::control_character("vitalik\0.eth", vec![], Some(ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0))))]
+fn process_partial_tests(
+    #[case] name: &str,
+    #[case] expected_labels: Vec<&str>,
+    #[case] expected_error: Option<ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let (labels, error) = normalizer.process_partial(name);
+    let labels = labels
+        .iter()
+        .map(|label| join_labels(std::slice::from_ref(label)))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, expected_labels);
+    assert_eq!(error, expected_error);
+}
+
+#[rstest]
+#[case::remove(DisallowedPolicy::Remove, "a!b.vitalik", vec!["ab", "vitalik"], None)]
+#[case::replace(DisallowedPolicy::Replace('\u{FFFD}'), "a!b.vitalik", vec!["a\u{FFFD}b", "vitalik"], None)]
+#[case::still_validated(
+    DisallowedPolicy::Remove,
+    "a!b.vitalik.a_b",
+    vec!["ab", "vitalik"],
+    Some(currable_error(CurrableError::UnderscoreInMiddle, 1, "_", Some("")))
+)]
+fn process_partial_disallowed_policy_tests(
+    #[case] policy: DisallowedPolicy,
+    #[case] name: &str,
+    #[case] expected_labels: Vec<&str>,
+    #[case] expected_error: Option<ProcessError>,
+) {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(policy);
+    let (labels, error) = normalizer.process_partial(name);
+    let labels = labels
+        .iter()
+        .map(|label| join_labels(std::slice::from_ref(label)))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, expected_labels);
+    assert_eq!(error, expected_error);
+}
+
+#[rstest]
+#[case::valid("Vitalik.eth", vec!["vitalik", "eth"], vec![])]
+#[case::all_errors(
+    "a_b.vitalik..eth!",
+    vec!["vitalik"],
+    vec![
+        (0, currable_error(CurrableError::UnderscoreInMiddle, 1, "_", Some(""))),
+        (2, empty_label()),
+        (3, disallowed("!", 3)),
+    ],
+)]
+#[case::empty("", vec![], vec![])]
+#[case::control_character("vitalik\0.eth", vec![], vec![(0, ProcessError::DisallowedSequence(DisallowedSequence::ControlCharacter(0)))])]
+fn process_lenient_tests(
+    #[case] name: &str,
+    #[case] expected_labels: Vec<&str>,
+    #[case] expected_errors: Vec<(usize, ProcessError)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let (labels, errors) = normalizer.process_lenient(name);
+    let labels = labels
+        .iter()
+        .map(|label| join_labels(std::slice::from_ref(label)))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, expected_labels);
+    assert_eq!(errors, expected_errors);
+}
+
+#[rstest]
+fn validate_name_all_is_public(normalizer: &EnsNameNormalizer) {
+    let tokenized = normalizer.tokenize("a_b.eth").unwrap();
+    let results = ens_normalize_rs::validate_name_all(
+        &tokenized,
+        normalizer.specs(),
+        &ens_normalize_rs::ValidationOptions::default(),
+    );
+    let results = results
+        .into_iter()
+        .map(|(index, result)| (index, result.map(|label| join_labels(&[label]))))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        vec![
+            (
+                0,
+                Err(currable_error(
+                    CurrableError::UnderscoreInMiddle,
+                    1,
+                    "_",
+                    Some("")
+                ))
+            ),
+            (1, Ok("eth".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn process_lenient_applies_disallowed_policy() {
+    let normalizer = EnsNameNormalizer::default().on_disallowed(DisallowedPolicy::Remove);
+    let (labels, errors) = normalizer.process_lenient("a!b.a_b.eth");
+    let labels = labels
+        .iter()
+        .map(|label| join_labels(std::slice::from_ref(label)))
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["ab", "eth"]);
+    assert_eq!(
+        errors,
+        vec![(
+            1,
+            currable_error(CurrableError::UnderscoreInMiddle, 1, "_", Some(""))
+        )]
+    );
+}
+
+#[rstest]
+#[case::exact("admin.eth", true)]
+#[case::mapped("ＡＤＭＩＮ.eth", true)]
+#[case::ignored("ad\u{AD}min.eth", true)]
+#[case::other("vitalik.eth", false)]
+fn is_blocked_tests(#[case] name: &str, #[case] expected: bool, normalizer: &EnsNameNormalizer) {
+    let blocklist = HashSet::from(["admin.eth".to_string()]);
+    assert_eq!(normalizer.is_blocked(name, &blocklist), Ok(expected));
+}
+
+#[rstest]
+#[case::same("admin.eth", true)]
+#[case::rn_looks_like_m("adrnin.eth", true)]
+#[case::other("vitalik.eth", false)]
+fn normalize_skeleton_for_blocklist_tests(
+    #[case] name: &str,
+    #[case] expected: bool,
+    normalizer: &EnsNameNormalizer,
+) {
+    let blocklist = HashSet::from([normalizer
+        .normalize_skeleton_for_blocklist("admin.eth")
+        .unwrap()]);
+    let skeleton = normalizer.normalize_skeleton_for_blocklist(name).unwrap();
+    assert_eq!(blocklist.contains(&skeleton), expected);
+}
+
+#[rstest]
+#[case::canonical("vitalik.eth", Ok(()))]
+#[case::canonical_emoji("💩.eth", Ok(()))]
+#[case::mapped("viTalik.eth", Err(not_canonical(2, "t", "T")))]
+#[case::ignored("vit\u{AD}alik.eth", Err(not_canonical(3, "", "\u{AD}")))]
+#[case::nfc("cafe\u{301}.eth", Err(not_canonical(3, "é", "e\u{301}")))]
+#[case::emoji_fe0f("a.💩\u{FE0F}", Err(not_canonical(2, "💩", "💩\u{FE0F}")))]
+#[case::invalid("vitalik..eth", Err(empty_label()))]
+fn validate_canonical_tests(
+    #[case] name: &str,
+    #[case] expected: Result<(), ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    assert_eq!(normalizer.validate_canonical(name), expected);
+}
+
+fn not_canonical(index: usize, expected: &str, found: &str) -> ProcessError {
+    ProcessError::NotCanonical {
+        index,
+        expected: expected.to_string(),
+        found: found.to_string(),
+    }
+}
+
+#[rstest]
+fn normalize_batch_atomic_tests(normalizer: &EnsNameNormalizer) {
+    assert_eq!(
+        normalizer.normalize_batch_atomic(["Vitalik.eth", "💩.eth"]),
+        Ok(vec!["vitalik.eth".to_string(), "💩.eth".to_string()])
+    );
+    assert_eq!(
+        normalizer.normalize_batch_atomic(["vitalik.eth", "vitalik..eth", "ok.eth", "a b"]),
+        Err(vec![(1, empty_label()), (3, disallowed(" ", 1))])
+    );
+    assert_eq!(
+        normalizer.normalize_batch_atomic(Vec::<String>::new()),
+        Ok(vec![])
+    );
+}
+
+#[rstest]
+fn labels_iter_tests(normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process("vitalik.💩.eth").unwrap();
+    let types = processed
+        .labels_iter()
+        .map(|(index, label)| (index, label.label_type.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            (0, LabelType::Ascii),
+            (1, LabelType::Emoji),
+            (2, LabelType::Ascii)
+        ]
+    );
+    assert_eq!(processed.label(1), processed.labels.get(1));
+    assert_eq!(processed.label(3), None);
+}
+
+#[cfg(feature = "namehash")]
+#[rstest]
+#[case::name("vitalik.eth", Ok(VITALIK_ETH_NAMEHASH))]
+#[case::trailing_dot("Vitalik.eth.", Ok(VITALIK_ETH_NAMEHASH))]
+#[case::empty("", Ok(ROOT_NAMEHASH))]
+#[case::root(".", Ok(ROOT_NAMEHASH))]
+#[case::two_trailing_dots("vitalik.eth..", Err(empty_label()))]
+fn namehash_dns_tests(
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer
+        .namehash_dns(name)
+        .map(|hash| hash.iter().map(|b| format!("{b:02x}")).collect::<String>());
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[cfg(feature = "namehash")]
+#[rstest]
+#[case::name("vitalik.eth", Ok(VITALIK_ETH_NAMEHASH))]
+#[case::mapped("VITALIK.ETH", Ok(VITALIK_ETH_NAMEHASH))]
+#[case::eth(
+    "eth",
+    Ok("93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae")
+)]
+#[case::empty("", Ok(ROOT_NAMEHASH))]
+#[case::trailing_dot("vitalik.eth.", Err(empty_label()))]
+#[case::disallowed("vitalik!.eth", Err(disallowed("!", 7)))]
+fn namehash_tests(
+    #[case] name: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let hex = |hash: [u8; 32]| hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let actual = normalizer.namehash(name).map(hex);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+    assert_eq!(ens_normalize_rs::namehash(name).map(hex), actual);
+    if let Ok(processed) = normalizer.process(name) {
+        assert_eq!(Ok(hex(processed.namehash())), actual);
+        assert_eq!(
+            Ok(hex(ens_normalize_rs::namehash_from_labels(
+                &processed.labels
+            ))),
+            actual
+        );
+    }
+}
+
+#[cfg(feature = "namehash")]
+#[rstest]
+#[case::eth(
+    "eth",
+    Ok("4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0")
+)]
+#[case::mapped(
+    "ETH",
+    Ok("4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0")
+)]
+#[case::stop("vitalik.eth", Err(disallowed(".", 7)))]
+#[case::empty("", Err(empty_label()))]
+#[case::only_ignored("\u{AD}", Err(empty_label()))]
+#[case::disallowed("vitalik!", Err(disallowed("!", 7)))]
+fn labelhash_tests(
+    #[case] label: &str,
+    #[case] expected: Result<&str, ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let hex = |hash: [u8; 32]| hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let actual = normalizer.labelhash(label).map(hex);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+    assert_eq!(ens_normalize_rs::labelhash(label).map(hex), actual);
+}
+
+#[cfg(feature = "namehash")]
+#[rstest]
+#[case::name("Vitalik.eth", vec!["af2caa1c2ca1d027f1ac823b529d0a67cd144264b2789fa2ea4d63a67c7103cc", "4f5b812789fc606be1b3b16908db13fc7a9adf7ca72641f84d75b47069d3d7f0"])]
+#[case::empty("", vec![])]
+fn labelhashes_tests(
+    #[case] name: &str,
+    #[case] expected: Vec<&str>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let hex = |hash: &[u8; 32]| hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let processed = normalizer.process(name).unwrap();
+    let actual = processed.labelhashes();
+    assert_eq!(actual.iter().map(hex).collect::<Vec<_>>(), expected);
+}
+
+#[cfg(feature = "namehash")]
+#[rstest]
+#[case::name("Vitalik.eth", Ok(("vitalik.eth", VITALIK_ETH_NAMEHASH)))]
+#[case::empty("", Ok(("", ROOT_NAMEHASH)))]
+#[case::empty_label("vitalik..eth", Err(empty_label()))]
+fn normalize_and_namehash_tests(
+    #[case] name: &str,
+    #[case] expected: Result<(&str, &str), ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer
+        .normalize_and_namehash(name)
+        .map(|(normalized, hash)| {
+            let hash = hash.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            (normalized, hash)
+        });
+    let expected = expected.map(|(normalized, hash)| (normalized.to_string(), hash.to_string()));
+    assert_eq!(actual, expected);
+}
+
+#[cfg(feature = "namehash")]
+const VITALIK_ETH_NAMEHASH: &str =
+    "ee6c4522aab0003e8d14cd40a6af439055fd2577951148c14b6cea9a53475835";
+#[cfg(feature = "namehash")]
+const ROOT_NAMEHASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[rstest]
+#[case::at_limit("😀😀😀.eth", Ok("😀😀😀.eth"))]
+#[case::over_limit("😀😀😀😀.eth", Err(too_many_consecutive_emoji(4)))]
+#[case::different_emoji("😀😀😀💩😀😀😀.eth", Ok("😀😀😀💩😀😀😀.eth"))]
+#[case::split_by_text("😀😀😀a😀😀😀.eth", Ok("😀😀😀a😀😀😀.eth"))]
+#[case::with_fe0f("❤️❤❤️❤.eth", Err(too_many_consecutive_emoji(4)))]
+fn max_consecutive_emoji_tests(#[case] name: &str, #[case] expected: Result<&str, ProcessError>) {
+    let normalizer = EnsNameNormalizer::default().max_consecutive_emoji(3);
+    let actual = normalizer.normalize(name);
+    assert_eq!(actual, expected.map(|s| s.to_string()));
+}
+
+#[rstest]
+#[case::ascii("Vitalik.eth", Ok(("vitalik.eth", Some(LabelType::Ascii))))]
+#[case::emoji("💩.eth", Ok(("💩.eth", Some(LabelType::Emoji))))]
+#[case::subdomain("привет.vitalik.eth", Ok(("привет.vitalik.eth", Some(LabelType::Ascii))))]
+#[case::cyrillic_2ld("привет.eth", Ok(("привет.eth", Some(LabelType::Other("Cyrillic".to_string())))))]
+#[case::tld("eth", Ok(("eth", None)))]
+#[case::empty("", Ok(("", None)))]
+#[case::invalid("vitalik..eth", Err(empty_label()))]
+fn normalize_with_group_tests(
+    #[case] name: &str,
+    #[case] expected: Result<(&str, Option<LabelType>), ProcessError>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.normalize_with_group(name);
+    assert_eq!(
+        actual,
+        expected.map(|(normalized, group)| (normalized.to_string(), group))
+    );
+}
+
+#[rstest]
+#[case("vitalik.eth")]
+#[case("-ξ1⃣")]
+#[case("🅰️🅱.eth")]
+fn beautify_cps_only_tests(#[case] name: &str, normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process(name).unwrap();
+    let stored = processed.labels.clone();
+    assert_eq!(normalizer.beautify_cps_only(&stored), processed.beautify());
+    assert_eq!(beautify_labels(&stored), processed.beautify());
+    assert_eq!(join_labels(&stored), processed.normalize());
+}
+
+#[rstest]
+#[case::already_normalized("vitalik.eth", Some(true))]
+#[case::emoji_without_fe0f("🅰🅱.eth", Some(true))]
+#[case::empty("", Some(true))]
+#[case::uppercase("Vitalik.eth", Some(false))]
+#[case::emoji_with_fe0f("🅰️🅱.eth", Some(false))]
+#[case::ignored("vit\u{AD}alik.eth", Some(false))]
+#[case::composed("cafe\u{301}.eth", Some(false))]
+#[case::invalid("vitalik..eth", None)]
+fn normalize_cow_tests(
+    #[case] name: &str,
+    #[case] expected_borrowed: Option<bool>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.normalize_cow(name);
+    match expected_borrowed {
+        Some(expected_borrowed) => {
+            let actual = actual.expect("normalize should succeed");
+            assert_eq!(matches!(actual, Cow::Borrowed(_)), expected_borrowed);
+            assert_eq!(actual, normalizer.normalize(name).unwrap());
+            assert_eq!(normalizer.is_normalized(name), expected_borrowed);
+            let (normalized, changed) = normalizer.normalize_checked(name).unwrap();
+            assert_eq!(normalized, actual);
+            assert_eq!(changed, !expected_borrowed);
+            assert_eq!(changed, normalized != name);
+        }
+        None => {
+            assert!(actual.is_err());
+            assert!(!normalizer.is_normalized(name));
+            assert!(normalizer.normalize_checked(name).is_err());
+        }
+    }
+}
+
+#[rstest]
+#[case::replacement_char("\u{FFFD}")]
+#[case::max_code_point("\u{10FFFF}.eth")]
+#[case::private_use("\u{E000}\u{F8FF}")]
+#[case::nul_and_controls("\0\u{1}\u{7F}\u{9F}")]
+#[case::only_fe0f("\u{FE0F}\u{FE0F}\u{FE0F}")]
+#[case::only_zwj("\u{200D}\u{200D}")]
+#[case::dangling_zwj_emoji("👨\u{200D}\u{200D}💻\u{200D}")]
+#[case::keycap_fragments("\u{20E3}1\u{FE0F}\u{20E3}\u{20E3}")]
+#[case::regional_indicator("\u{1F1E6}")]
+#[case::only_stops("....")]
+#[case::fenced_only("’・⁄")]
+fn no_panic_tests(#[case] name: &str, normalizer: &EnsNameNormalizer) {
+    assert_no_panic(normalizer, name);
+}
+
+#[rstest]
+fn no_panic_long_inputs_tests(normalizer: &EnsNameNormalizer) {
+    let combining_run = format!("a{}", "\u{301}".repeat(10_000));
+    let combining_only = "\u{300}\u{301}\u{302}".repeat(1_000);
+    let emoji_zwj_run = "👨\u{200D}".repeat(1_000);
+    let long_label = "a".repeat(100_000);
+    for name in [combining_run, combining_only, emoji_zwj_run, long_label] {
+        assert_no_panic(normalizer, &name);
+    }
+}
+
+fn assert_no_panic(normalizer: &EnsNameNormalizer, name: &str) {
+    let _ = normalizer.tokenize(name);
+    if let Ok(processed) = normalizer.process(name) {
+        let _ = processed.normalize();
+        let _ = processed.beautify();
+    }
+}
+
+#[rstest]
+#[case::ascii("ab.eth", vec![('a', Some(0..1)), ('b', Some(1..2)), ('.', Some(2..3)), ('e', Some(3..4)), ('t', Some(4..5)), ('h', Some(5..6))])]
+#[case::mapped_and_ignored("A\u{AD}b", vec![('a', Some(0..1)), ('b', Some(3..4))])]
+#[case::mapped_to_many("™", vec![('t', Some(0..3)), ('m', Some(0..3))])]
+#[case::emoji("x💩\u{FE0F}", vec![('x', Some(0..1)), ('💩', Some(1..8))])]
+#[case::nfc("a\u{304}", vec![('ā', None)])]
+fn source_map_tests(
+    #[case] name: &str,
+    #[case] expected: Vec<(char, Option<Range<usize>>)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(name).expect("process should succeed");
+    let source_map = processed.source_map();
+    assert_eq!(source_map, expected);
+    assert_eq!(
+        source_map.iter().map(|(c, _)| c).collect::<String>(),
+        processed.normalize()
+    );
+}
+
+#[rstest]
+#[case::same("🅰🅱.eth", "🅰🅱.eth", true)]
+#[case::fe0f_moved("🅰️🅱.eth", "🅰🅱️.eth", true)]
+#[case::zwj_sequence("vitalik❤️‍🔥.eth", "vitalik❤‍🔥.eth", true)]
+#[case::different_emoji("🅰🅱.eth", "🅱🅰.eth", false)]
+#[case::case_sensitive("🅰️Vitalik", "🅰vitalik", false)]
+fn names_emoji_equivalent_tests(
+    #[case] a: &str,
+    #[case] b: &str,
+    #[case] expected: bool,
+    normalizer: &EnsNameNormalizer,
+) {
+    assert_eq!(normalizer.names_emoji_equivalent(a, b), Ok(expected));
+}
+
+#[rstest]
+#[case::valid("vitalik.eth", None)]
+#[case::underscore("vitalik__", Some(("underscore_in_middle", Some(7))))]
+#[case::fenced("・abcd", Some(("fenced_leading", Some(0))))]
+#[case::empty_label("vitalik..eth", Some(("empty_label", None)))]
+#[case::disallowed("vitalik .eth", Some(("invalid", Some(7))))]
+#[case::nul("vitalik\0.eth", Some(("control_character", None)))]
+#[case::c1_control("vitalik\u{85}.eth", Some(("control_character", None)))]
+fn error_code_tests(
+    #[case] name: &str,
+    #[case] expected: Option<(&str, Option<usize>)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer
+        .process(name)
+        .err()
+        .map(|err| (err.code(), err.index()));
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::fenced_consecutive("a・・a.eth", Some((true, Some(1), Some("・"))))]
+#[case::underscore_in_middle("a_b.eth", Some((true, Some(1), Some(""))))]
+#[case::empty_label("vitalik..eth", Some((false, None, None)))]
+#[case::valid("vitalik.eth", None)]
+fn suggestion_tests(
+    #[case] name: &str,
+    #[case] expected: Option<(bool, Option<usize>, Option<&str>)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer.process(name).err();
+    let actual = actual
+        .as_ref()
+        .map(|err| (err.is_curable(), err.index(), err.suggestion()));
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::single_emoji_label("💩", true)]
+#[case::all_emoji_labels("💩.🔥👨\u{200D}💻", true)]
+#[case::emoji_with_tld("💩.eth", false)]
+#[case::mixed_label("💩a", false)]
+#[case::empty("", false)]
+fn is_all_emoji_tests(#[case] name: &str, #[case] expected: bool, normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process(name).expect("process should succeed");
+    assert_eq!(processed.is_all_emoji(), expected);
+}
+
+#[rstest]
+#[case::fullwidth_ascii("ｖｉｔａｌｉｋ.eth", true)]
+#[case::fullwidth_digits("１２３.eth", true)]
+#[case::halfwidth_katakana("ｱ.eth", true)]
+#[case::uppercase("Vitalik.eth", false)]
+#[case::plain("vitalik.eth", false)]
+fn used_fullwidth_tests(
+    #[case] name: &str,
+    #[case] expected: bool,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(name).expect("process should succeed");
+    assert_eq!(processed.used_fullwidth(), expected);
+}
+
+#[rstest]
+#[case::mapped("Vitalik.eth", "ｖitalik.eth", vec![(0, mapped("V", "ｖ"))])]
+#[case::nfc("café.eth", "cafe\u{301}.eth", vec![(3, CollisionReason::Nfc {
+    a: "é".to_string(),
+    b: "e\u{301}".to_string(),
+})])]
+#[case::emoji("👍.eth", "👍\u{FE0F}.eth", vec![(0, CollisionReason::Emoji {
+    a: "👍".to_string(),
+    b: "👍\u{FE0F}".to_string(),
+})])]
+#[case::ignored("vitalik.eth", "vita\u{AD}lik.eth", vec![(4, CollisionReason::Ignored {
+    a: "".to_string(),
+    b: "\u{AD}".to_string(),
+})])]
+#[case::several("A\u{AD}b.eth", "aB.eth", vec![
+    (0, mapped("A", "a")),
+    (1, CollisionReason::Ignored { a: "\u{AD}".to_string(), b: "".to_string() }),
+    (1, mapped("b", "B")),
+])]
+#[case::identical("vitalik.eth", "vitalik.eth", vec![])]
+#[case::different_names("vitalik.eth", "nick.eth", vec![])]
+fn collision_explanation_tests(
+    #[case] a: &str,
+    #[case] b: &str,
+    #[case] expected: Vec<(usize, CollisionReason)>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let actual = normalizer
+        .collision_explanation(a, b)
+        .expect("both names should be valid");
+    assert_eq!(actual, expected);
+}
+
+fn mapped(a: &str, b: &str) -> CollisionReason {
+    CollisionReason::Mapped {
+        a: a.to_string(),
+        b: b.to_string(),
+    }
+}
+
+#[rstest]
+#[case::ascii("Abba.eth", vec!['.', 'a', 'b', 'e', 'h', 't'])]
+#[case::emoji("💩\u{FE0F}💩", vec!['💩'])]
+#[case::empty("", vec![])]
+fn code_point_set_tests(
+    #[case] name: &str,
+    #[case] expected: Vec<char>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(name).expect("process should succeed");
+    let expected = expected
+        .into_iter()
+        .map(|c| c as u32)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(processed.code_point_set(), expected);
+}
+
+#[rstest]
+fn require_spec_version_tests() {
+    let mut normalizer = EnsNameNormalizer::default();
+    let fingerprint = normalizer.specs().fingerprint().to_string();
+    assert_eq!(fingerprint.len(), 16);
+
+    normalizer.require_spec_version(&fingerprint);
+    assert_eq!(
+        normalizer.normalize("Vitalik.eth"),
+        Ok("vitalik.eth".to_string())
+    );
+
+    normalizer.require_spec_version("0000000000000000");
+    assert_eq!(
+        normalizer.normalize("Vitalik.eth"),
+        Err(ProcessError::SpecVersionMismatch {
+            expected: "0000000000000000".to_string(),
+            actual: fingerprint,
+        })
+    );
+}
+
+#[rstest]
+#[case::ascii("vitalik.eth", vec![])]
+#[case::emoji("🚀.eth", vec![])]
+#[case::greek_near_cyrillic("πτ.eth", vec![ConfusabilityNote { label_index: 0, group: "Cyrillic".to_string(), missing: 'π' as u32 }])]
+fn confusability_report_tests(
+    #[case] input: &str,
+    #[case] expected: Vec<ConfusabilityNote>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(input).expect("name should be valid");
+    assert_eq!(processed.confusability_report(normalizer.specs()), expected);
+}
+
+#[rstest]
+#[case::ascii("Vitalik.eth", "vitalik.eth")]
+#[case::skin_tone("👍🏻.eth", "👍.eth")]
+#[case::greek("ωμέγα.eth", "ωμέya.eth")]
+#[case::cyrillic("привет.eth", "пpᴎвeᴛ.eth")]
+fn search_key_tests(#[case] input: &str, #[case] expected: &str, normalizer: &EnsNameNormalizer) {
+    let processed = normalizer.process(input).expect("name should be valid");
+    assert_eq!(processed.search_key(normalizer.specs()), expected);
+}
+
+#[rstest]
+#[case::unchanged("vitalik.eth", vec![keep("vitalik.eth")])]
+#[case::mapped("Vitalik.eth", vec![replace("V", "v"), keep("italik.eth")])]
+#[case::ignored("vit\u{AD}alik.eth", vec![keep("vit"), remove("\u{AD}"), keep("alik.eth")])]
+#[case::nfc("a\u{304}.eth", vec![replace("a\u{304}", "\u{101}"), keep(".eth")])]
+#[case::emoji_fe0f("🅰️.eth", vec![replace("🅰\u{FE0F}", "🅰"), keep(".eth")])]
+fn input_vs_normalized_diff_tests(
+    #[case] input: &str,
+    #[case] expected: Vec<DiffOp>,
+    normalizer: &EnsNameNormalizer,
+) {
+    let processed = normalizer.process(input).expect("name should be valid");
+    assert_eq!(processed.input_vs_normalized_diff(), expected);
+}
+
+fn keep(s: &str) -> DiffOp {
+    DiffOp::Keep(s.to_string())
+}
+
+fn replace(from: &str, to: &str) -> DiffOp {
+    DiffOp::Replace {
+        from: from.to_string(),
+        to: to.to_string(),
+    }
+}
+
+fn remove(s: &str) -> DiffOp {
+    DiffOp::Remove(s.to_string())
+}
+
+#[rstest]
+fn label_report_tests(normalizer: &EnsNameNormalizer) {
+    let processed = normalizer
+        .process("Vitalik.\u{16a0}\u{16a1}.💩.привет")
+        .expect("name should be valid");
+    let report = processed
+        .label_report()
+        .into_iter()
+        .map(|label| (label.normalized, label.label_type, label.restricted))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        report,
+        vec![
+            ("vitalik".to_string(), LabelType::Ascii, false),
+            (
+                "\u{16a0}\u{16a1}".to_string(),
+                LabelType::Other("Runr".to_string()),
+                true
+            ),
+            ("💩".to_string(), LabelType::Emoji, false),
+            (
+                "привет".to_string(),
+                LabelType::Other("Cyrillic".to_string()),
+                false
+            ),
+        ]
+    );
+}
+
+fn disallowed(sequence: &str, index: usize) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::Invalid {
+        sequence: sequence.to_string(),
+        index,
+    })
+}
+
+fn label_too_short(label_index: usize, len: usize) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::LabelTooShort { label_index, len })
+}
+
+fn emoji_not_allowed(cps: &[u32]) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::EmojiNotAllowed { cps: cps.to_vec() })
+}
+
+fn too_many_consecutive_emoji(count: usize) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::TooManyConsecutiveEmoji { count })
+}
+
+fn too_many_labels(count: usize, max: usize) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::TooManyLabels { count, max })
+}
+
+fn not_fully_emoji() -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::NotFullyEmoji)
+}
+
+fn unknown_emoji(sequence: &str) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::UnknownEmoji {
+        cps: sequence.chars().map(|c| c as u32).collect(),
+    })
+}
+
+fn not_nfc(index: usize) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::NotNfc { index })
+}
+
+fn invisible(cp: u32) -> ProcessError {
+    ProcessError::DisallowedSequence(DisallowedSequence::InvisibleCharacter(cp))
 }
 
 fn empty_label() -> ProcessError {
     ProcessError::DisallowedSequence(DisallowedSequence::EmptyLabel)
 }
 
+fn fenced_leading(name: &str) -> CurrableError {
+    CurrableError::FencedLeading {
+        name: name.to_string(),
+    }
+}
+
+fn fenced_trailing(name: &str) -> CurrableError {
+    CurrableError::FencedTrailing {
+        name: name.to_string(),
+    }
+}
+
+fn fenced_consecutive(name: &str) -> CurrableError {
+    CurrableError::FencedConsecutive {
+        name: name.to_string(),
+    }
+}
+
 fn currable_error(
     inner: CurrableError,
     index: usize,
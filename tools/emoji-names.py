@@ -0,0 +1,52 @@
+#!/usr/bin/env python3
+"""
+Emoji short names table generator.
+
+This script builds src/static_data/emoji_names.json, used by the `emoji-names`
+feature, from the CLDR short names listed in Unicode's emoji-test.txt.
+"""
+
+import argparse
+import json
+
+import requests
+
+
+def parse_emoji_test(content: str) -> list:
+    """Extract fully-qualified emoji and their short names."""
+    names = []
+    for line in content.splitlines():
+        if not line or line.startswith('#') or '; fully-qualified' not in line:
+            continue
+        cps, comment = line.split(';', 1)[0], line.split('#', 1)[1]
+        # comment looks like "😀 E1.0 grinning face"
+        name = comment.strip().split(' ', 2)[2]
+        names.append({'emoji': [int(cp, 16) for cp in cps.split()], 'name': name})
+    return names
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Generate emoji short names table")
+    parser.add_argument("--version", default="16.0", help="Unicode emoji version")
+    parser.add_argument("--output", default="src/static_data/emoji_names.json")
+    parser.add_argument("--input", help="local emoji-test.txt to read instead of downloading it")
+    args = parser.parse_args()
+
+    if args.input:
+        with open(args.input, encoding='utf-8') as f:
+            content = f.read()
+    else:
+        url = f"https://unicode.org/Public/emoji/{args.version}/emoji-test.txt"
+        print(f"Downloading {url}...")
+        response = requests.get(url, timeout=30)
+        response.raise_for_status()
+        content = response.text
+
+    names = parse_emoji_test(content)
+    with open(args.output, 'w', encoding='utf-8') as f:
+        json.dump({'unicode': args.version, 'names': names}, f, ensure_ascii=False, indent=1)
+    print(f"Wrote {len(names)} names to {args.output}")
+
+
+if __name__ == "__main__":
+    main()
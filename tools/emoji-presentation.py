@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+"""
+Emoji presentation table generator.
+
+This script rewrites the `EMOJI_PRESENTATION` table of src/code_points/presentation.rs
+from the `Emoji_Presentation` property listed in Unicode's emoji-data.txt.
+By default the Unicode version is the one of src/static_data/spec.json, so run it
+after tools/unicode-update.py to keep both in sync.
+"""
+
+import argparse
+import json
+import re
+
+import requests
+
+TABLE_PATTERN = re.compile(
+    r"(/// Ranges of code points with `Emoji_Presentation=Yes` from `emoji-data.txt` \(Unicode )"
+    r"[^)]*"
+    r"(\),\n.*?const EMOJI_PRESENTATION: &\[\(CodePoint, CodePoint\)\] = &\[\n)"
+    r".*?"
+    r"(\];)",
+    re.DOTALL,
+)
+
+
+def parse_emoji_presentation(content: str) -> list:
+    """Extract sorted `Emoji_Presentation` ranges, merging adjacent ones."""
+    ranges = []
+    for line in content.splitlines():
+        line = line.split('#', 1)[0].strip()
+        if not line:
+            continue
+        cps, prop = [part.strip() for part in line.split(';', 1)]
+        if prop != 'Emoji_Presentation':
+            continue
+        start, _, end = cps.partition('..')
+        ranges.append((int(start, 16), int(end or start, 16)))
+    merged = []
+    for start, end in sorted(ranges):
+        if merged and merged[-1][1] + 1 >= start:
+            merged[-1] = (merged[-1][0], max(merged[-1][1], end))
+        else:
+            merged.append((start, end))
+    return merged
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Generate emoji presentation table")
+    parser.add_argument("--version", help="Unicode version, defaults to the one of --spec")
+    parser.add_argument("--spec", default="src/static_data/spec.json")
+    parser.add_argument("--output", default="src/code_points/presentation.rs")
+    parser.add_argument("--input", help="local emoji-data.txt to read instead of downloading it")
+    args = parser.parse_args()
+
+    if not args.version:
+        with open(args.spec, encoding='utf-8') as f:
+            # e.g. "16.0.0 (2024-09-10T20:47:54.200Z)"
+            args.version = json.load(f)['unicode'].split()[0]
+
+    if args.input:
+        with open(args.input, encoding='utf-8') as f:
+            content = f.read()
+    else:
+        url = f"https://www.unicode.org/Public/{args.version}/ucd/emoji/emoji-data.txt"
+        print(f"Downloading {url}...")
+        response = requests.get(url, timeout=30)
+        response.raise_for_status()
+        content = response.text
+
+    ranges = parse_emoji_presentation(content)
+    rows = ''.join(f"    (0x{start:04X}, 0x{end:04X}),\n" for start, end in ranges)
+    major_minor = '.'.join(args.version.split('.')[:2])
+
+    with open(args.output, encoding='utf-8') as f:
+        source = f.read()
+    source, count = TABLE_PATTERN.subn(
+        lambda m: f"{m.group(1)}{major_minor}{m.group(2)}{rows}{m.group(3)}", source
+    )
+    if count != 1:
+        raise SystemExit(f"EMOJI_PRESENTATION table not found in {args.output}")
+    with open(args.output, 'w', encoding='utf-8') as f:
+        f.write(source)
+    print(f"Wrote {len(ranges)} ranges to {args.output}")
+
+
+if __name__ == "__main__":
+    main()